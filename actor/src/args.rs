@@ -9,4 +9,21 @@ pub struct Args {
     pub model_def_path: PathBuf,
     #[arg(short, long)]
     pub gpu: bool,
+    /// Leave the priorities of new transitions for the replay server to
+    /// assign, instead of computing their td errors.
+    ///
+    /// Under the replay server's default `supplied` initial priority, the
+    /// new transitions then get the current max priority. Under any other
+    /// initial priority the td errors would be ignored anyway, so skipping
+    /// them only saves the forward passes
+    #[arg(short, long)]
+    pub skip_initial_priorities: bool,
+    /// Number of games to play side by side. Their states are passed to the
+    /// model in a single batch.
+    ///
+    /// The game windows all open full-size on the same X display, so more
+    /// than one game needs a compositing manager running there. Without one,
+    /// the covered parts of the windows would be grabbed as undefined frames
+    #[arg(short, long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pub env_count: u32,
 }
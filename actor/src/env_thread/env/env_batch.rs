@@ -0,0 +1,150 @@
+use super::episode::{Done, Status};
+use super::message_bridge::{MessageBridge, Replies, Reply, Request};
+use super::{Env, EnvThreadMessage, PendingTransition, StateAccum, StepError};
+use crate::GameThreadMessage;
+use crossbeam_channel::{Receiver, Sender};
+use image::ImageOwned2;
+
+// Steps the envs of several games at once, so that their states can be passed
+// to the model in a single batch.
+// A requested hold waits for the episode of every env to end. Envs whose
+// episode ends before the others' are left idle until then
+pub struct EnvBatch<State>
+where
+    State: StateAccum<Frame = ImageOwned2>,
+{
+    bridge: MessageBridge,
+    envs: Vec<Env<State>>,
+    idle: Vec<bool>,
+    waiting_hold: bool,
+    // episode ids are shared by all envs, so that they are unique among the
+    // episodes of the actor
+    next_episode_id: u64,
+}
+
+impl<State> EnvBatch<State>
+where
+    State: StateAccum<Frame = ImageOwned2>,
+    <State as StateAccum>::View: Clone,
+{
+    pub fn new(
+        receiver: Receiver<EnvThreadMessage>,
+        game_thread_senders: Vec<Sender<GameThreadMessage>>,
+        actor_id: usize,
+        first_episode_id: u64,
+        state_settings: State::Settings,
+    ) -> Result<Self, StepError> {
+        let (bridge, replies) = MessageBridge::new(receiver, game_thread_senders)?;
+        let Replies {
+            replies,
+            received_wait_for_hold,
+        } = replies;
+        let mut next_episode_id = first_episode_id;
+        let envs = replies
+            .into_iter()
+            .map(|reply| {
+                let env = Env::new(
+                    reply.frame,
+                    reply.score,
+                    actor_id,
+                    next_episode_id,
+                    state_settings,
+                );
+                next_episode_id += 1;
+                env
+            })
+            .collect::<Vec<_>>();
+        Ok(Self {
+            bridge,
+            idle: vec![false; envs.len()],
+            envs,
+            waiting_hold: received_wait_for_hold,
+            next_episode_id,
+        })
+    }
+    fn active_env_indices(&self) -> Vec<usize> {
+        (0..self.envs.len()).filter(|i| !self.idle[*i]).collect()
+    }
+    // the states of the envs that aren't idle, in the order in which step
+    // takes their actions
+    pub fn states(&self) -> Vec<State::View> {
+        self.active_env_indices()
+            .into_iter()
+            .map(|i| self.envs[i].state())
+            .collect()
+    }
+    // the state of the first env, whose game is the one that the ui shows. It
+    // stays on its last state while the env is idle
+    pub fn shown_state(&self) -> State::View {
+        self.envs[0].state()
+    }
+    pub fn step(&mut self, actions: &[u8]) -> Result<(), StepError> {
+        let env_indices = self.active_env_indices();
+        assert_eq!(
+            actions.len(),
+            env_indices.len(),
+            "every env that isn't idle must be given an action"
+        );
+        let requests = env_indices
+            .iter()
+            .zip(actions)
+            .map(|(i, action)| (*i, Request::Action(*action)))
+            .collect();
+        let replies = self.send(requests)?;
+        let mut truncated_env_indices = vec![];
+        for ((i, action), reply) in env_indices.into_iter().zip(actions).zip(replies) {
+            let env = &mut self.envs[i];
+            let Status::Done(done_why) = env.step(*action, reply.frame, reply.score) else {
+                continue;
+            };
+            env.next_episode(self.next_episode_id);
+            self.next_episode_id += 1;
+            if self.waiting_hold {
+                self.idle[i] = true;
+            } else {
+                match done_why {
+                    Done::Terminated => {}
+                    Done::ShouldTruncate => truncated_env_indices.push(i),
+                }
+            }
+        }
+        if !truncated_env_indices.is_empty() {
+            self.truncate(truncated_env_indices)?;
+        }
+        if self.idle.iter().all(|idle| *idle) {
+            Err(StepError::WaitForHoldRequest)
+        } else {
+            Ok(())
+        }
+    }
+    fn truncate(&mut self, env_indices: Vec<usize>) -> Result<(), StepError> {
+        let requests = env_indices
+            .iter()
+            .map(|i| (*i, Request::Truncation))
+            .collect();
+        let replies = self.send(requests)?;
+        for (i, reply) in env_indices.into_iter().zip(replies) {
+            self.envs[i].truncate(reply.frame, reply.score);
+        }
+        Ok(())
+    }
+    fn send(&mut self, requests: Vec<(usize, Request)>) -> Result<Vec<Reply>, StepError> {
+        let Replies {
+            replies,
+            received_wait_for_hold,
+        } = self.bridge.send(requests)?;
+        if received_wait_for_hold {
+            self.waiting_hold = true;
+        }
+        Ok(replies)
+    }
+    // the id that a new batch should start from so that it doesn't reuse the
+    // ids of this one. An unfinished episode is never continued by another
+    // batch, so it keeps its id to itself
+    pub fn next_episode_id(&self) -> u64 {
+        self.next_episode_id
+    }
+    pub fn pop_transition(&mut self) -> Option<PendingTransition<State::View>> {
+        self.envs.iter_mut().find_map(Env::pop_transition)
+    }
+}
@@ -5,6 +5,9 @@ use replay_data::GenericTransition;
 use std::collections::VecDeque;
 use step_memory::StepMemory;
 
+pub const N_STEPS: usize = 3;
+pub const GAMMA: f64 = 0.99;
+
 pub struct BasicEpisode<State>
 where
     State: StateAccum,
@@ -19,12 +22,10 @@ where
     State: StateAccum,
     <State as StateAccum>::View: Clone,
 {
-    pub fn new(frame: State::Frame, score: u32) -> Self {
-        const N_STEPS: usize = 3;
-        const GAMMA: f64 = 0.99;
+    pub fn new(frame: State::Frame, score: u32, state_settings: State::Settings) -> Self {
         Self {
             step_memory: StepMemory::new(N_STEPS, GAMMA),
-            state: State::from_frame(frame),
+            state: State::from_frame(frame, state_settings),
             score,
         }
     }
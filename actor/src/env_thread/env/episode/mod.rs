@@ -3,7 +3,7 @@ mod state_accum;
 mod status;
 mod time_limited_wrapper;
 
-pub use basic_episode::BasicEpisode;
+pub use basic_episode::{BasicEpisode, GAMMA, N_STEPS};
 pub use state_accum::StateAccum;
 pub use status::{Done, Status};
 pub use time_limited_wrapper::TimeLimitedWrapper;
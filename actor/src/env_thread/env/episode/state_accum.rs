@@ -1,6 +1,9 @@
 pub trait StateAccum {
     type Frame;
     type View;
+    // what from_frame needs besides the frame, such as the number of frames
+    // to stack
+    type Settings: Copy;
     fn receive(&mut self, frame: Self::Frame);
     fn view(&self) -> Self::View;
     fn reset_to_current(&mut self);
@@ -11,5 +14,5 @@ pub trait StateAccum {
     // may allow those frames to be of type Self. The main difference between
     // from_frame and the standard from method is that from_frame does not have
     // an identity blanket impl, avoiding the potential for conflict
-    fn from_frame(frame: Self::Frame) -> Self;
+    fn from_frame(frame: Self::Frame, settings: Self::Settings) -> Self;
 }
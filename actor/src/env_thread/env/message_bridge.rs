@@ -20,12 +20,18 @@ impl From<Request> for GameThreadMessage {
 pub struct Reply {
     pub frame: ImageOwned2,
     pub score: u32,
+}
+
+pub struct Replies {
+    // in the order of the requests
+    pub replies: Vec<Reply>,
     pub received_wait_for_hold: bool,
 }
 
+// Passes requests to the game threads, one for each env, and their frames back
 pub struct MessageBridge {
     receiver: Receiver<EnvThreadMessage>,
-    game_thread_sender: Sender<GameThreadMessage>,
+    game_thread_senders: Vec<Sender<GameThreadMessage>>,
 }
 
 pub enum StepError {
@@ -34,33 +40,47 @@ pub enum StepError {
 }
 
 impl MessageBridge {
+    // waits for the first frame of every game
     pub fn new(
         receiver: Receiver<EnvThreadMessage>,
-        game_thread_sender: Sender<GameThreadMessage>,
-    ) -> Result<(Self, Reply), StepError> {
+        game_thread_senders: Vec<Sender<GameThreadMessage>>,
+    ) -> Result<(Self, Replies), StepError> {
         let bridge = Self {
             receiver,
-            game_thread_sender,
+            game_thread_senders,
         };
-        let reply = bridge.wait_for_next_reply()?;
-        Ok((bridge, reply))
+        let env_indices = (0..bridge.env_count()).collect::<Vec<_>>();
+        let replies = bridge.wait_for_replies(&env_indices)?;
+        Ok((bridge, replies))
     }
-    pub fn send(&self, request: Request) -> Result<Reply, StepError> {
-        self.game_thread_sender.send(request.into()).unwrap();
-        let reply = self.wait_for_next_reply()?;
-        Ok(reply)
+    // sends every request to the game of its env before waiting for any
+    // reply, so that the games advance side by side
+    pub fn send(&self, requests: Vec<(usize, Request)>) -> Result<Replies, StepError> {
+        let mut env_indices = Vec::with_capacity(requests.len());
+        for (env_index, request) in requests {
+            self.game_thread_senders[env_index]
+                .send(request.into())
+                .unwrap();
+            env_indices.push(env_index);
+        }
+        self.wait_for_replies(&env_indices)
     }
-    fn wait_for_next_reply(&self) -> Result<Reply, StepError> {
+    fn wait_for_replies(&self, env_indices: &[usize]) -> Result<Replies, StepError> {
+        let mut replies = std::iter::repeat_with(|| None)
+            .take(env_indices.len())
+            .collect::<Vec<_>>();
+        let mut pending_len = env_indices.len();
         let mut received_wait_for_hold = false;
-        loop {
+        while pending_len > 0 {
             match self.receiver.recv().unwrap() {
-                EnvThreadMessage::Frame((frame, score)) => {
-                    let reply = Reply {
-                        frame,
-                        score,
-                        received_wait_for_hold,
+                EnvThreadMessage::Frame(env_index, (frame, score)) => {
+                    let Some(position) = env_indices.iter().position(|i| *i == env_index) else {
+                        return Err(StepError::BadMessage);
                     };
-                    return Ok(reply);
+                    if replies[position].replace(Reply { frame, score }).is_some() {
+                        return Err(StepError::BadMessage);
+                    }
+                    pending_len -= 1;
                 }
                 EnvThreadMessage::Master(_) => return Err(StepError::BadMessage),
                 EnvThreadMessage::WaitForHold => {
@@ -68,6 +88,13 @@ impl MessageBridge {
                 }
             }
         }
+        Ok(Replies {
+            replies: replies.into_iter().map(Option::unwrap).collect(),
+            received_wait_for_hold,
+        })
+    }
+    pub fn env_count(&self) -> usize {
+        self.game_thread_senders.len()
     }
     pub const fn n_actions() -> u8 {
         const JUMP_ENABLED: bool = true;
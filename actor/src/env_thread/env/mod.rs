@@ -1,25 +1,31 @@
+mod env_batch;
 mod episode;
 mod message_bridge;
 
 use super::EnvThreadMessage;
-use crate::GameThreadMessage;
-use crossbeam_channel::{Receiver, Sender};
+pub use env_batch::EnvBatch;
 pub use episode::StateAccum;
-use episode::{BasicEpisode, Done, Status, TimeLimitedWrapper};
+use episode::{BasicEpisode, Status, TimeLimitedWrapper, GAMMA, N_STEPS};
 use image::ImageOwned2;
+use message_bridge::MessageBridge;
 pub use message_bridge::StepError;
-use message_bridge::{MessageBridge, Reply, Request};
-use replay_data::GenericTransition;
+use replay_data::{GenericTransition, TransitionMetadata};
 use std::collections::VecDeque;
 
+type PendingTransition<View> = (GenericTransition<View>, Option<u32>, TransitionMetadata);
+
+// The episodes of a single game. Its frames are passed in by the EnvBatch that
+// drives the game
 pub struct Env<State>
 where
     State: StateAccum<Frame = ImageOwned2>,
 {
-    bridge: MessageBridge,
     episode: TimeLimitedWrapper<State>,
-    pending_transitions: VecDeque<(GenericTransition<State::View>, Option<u32>)>,
-    waiting_hold: bool,
+    pending_transitions: VecDeque<PendingTransition<State::View>>,
+    actor_id: usize,
+    episode_id: u64,
+    episode_step: u32,
+    state_settings: State::Settings,
 }
 
 impl<State> Env<State>
@@ -27,68 +33,58 @@ where
     State: StateAccum<Frame = ImageOwned2>,
     <State as StateAccum>::View: Clone,
 {
-    pub fn new(
-        receiver: Receiver<EnvThreadMessage>,
-        game_thread_sender: Sender<GameThreadMessage>,
-    ) -> Result<Self, StepError> {
-        let (bridge, reply) = MessageBridge::new(receiver, game_thread_sender)?;
-        let Reply {
-            frame,
-            score,
-            received_wait_for_hold,
-        } = reply;
-        Ok(Self {
-            bridge,
-            episode: TimeLimitedWrapper::new(BasicEpisode::new(frame, score)),
+    fn new(
+        frame: ImageOwned2,
+        score: u32,
+        actor_id: usize,
+        episode_id: u64,
+        state_settings: State::Settings,
+    ) -> Self {
+        Self {
+            episode: TimeLimitedWrapper::new(BasicEpisode::new(frame, score, state_settings)),
             pending_transitions: VecDeque::new(),
-            waiting_hold: received_wait_for_hold,
-        })
+            actor_id,
+            episode_id,
+            episode_step: 0,
+            state_settings,
+        }
     }
-    pub fn step(&mut self, action: u8) -> Result<(), StepError> {
-        let (next_frame, next_score) = self.send(Request::Action(action))?;
-        let episode_status = self.episode.step(
-            action,
-            next_frame,
-            next_score,
-            &mut self.pending_transitions,
-        );
-        match episode_status {
-            Status::Running => Ok(()),
-            Status::Done(done_why) => {
-                if self.waiting_hold {
-                    Err(StepError::WaitForHoldRequest)
-                } else {
-                    match done_why {
-                        Done::Terminated => Ok(()),
-                        Done::ShouldTruncate => self.truncate(),
-                    }
-                }
-            }
+    fn step(&mut self, action: u8, next_frame: ImageOwned2, next_score: u32) -> Status {
+        let mut transitions = VecDeque::new();
+        let episode_status = self
+            .episode
+            .step(action, next_frame, next_score, &mut transitions);
+        for (transition, episode_score) in transitions {
+            let metadata =
+                TransitionMetadata::new(self.actor_id, self.episode_id, self.episode_step);
+            self.episode_step += 1;
+            self.pending_transitions
+                .push_back((transition, episode_score, metadata));
         }
+        episode_status
     }
-    fn truncate(&mut self) -> Result<(), StepError> {
-        let (frame, score) = self.send(Request::Truncation)?;
-        self.episode = TimeLimitedWrapper::new(BasicEpisode::new(frame, score));
-        Ok(())
+    // restarts the episode from the first frame of a restarted game
+    fn truncate(&mut self, frame: ImageOwned2, score: u32) {
+        self.episode =
+            TimeLimitedWrapper::new(BasicEpisode::new(frame, score, self.state_settings));
     }
-    fn send(&mut self, request: Request) -> Result<(State::Frame, u32), StepError> {
-        let Reply {
-            frame,
-            score,
-            received_wait_for_hold,
-        } = self.bridge.send(request)?;
-        if received_wait_for_hold {
-            self.waiting_hold = true;
-        }
-        Ok((frame, score))
+    fn next_episode(&mut self, episode_id: u64) {
+        self.episode_id = episode_id;
+        self.episode_step = 0;
     }
-    pub fn state(&self) -> State::View {
+    fn state(&self) -> State::View {
         self.episode.state()
     }
-    pub fn pop_transition(&mut self) -> Option<(GenericTransition<State::View>, Option<u32>)> {
+    fn pop_transition(&mut self) -> Option<PendingTransition<State::View>> {
         self.pending_transitions.pop_front()
     }
     pub const fn n_actions() -> u8 {
         MessageBridge::n_actions()
     }
+    pub const fn n_steps() -> usize {
+        N_STEPS
+    }
+    pub const fn gamma() -> f64 {
+        GAMMA
+    }
 }
@@ -5,14 +5,16 @@ mod param_updater_thread;
 mod state_accums;
 
 use crate::master_thread::ThreadType;
-use crate::{GameThreadMessage, MasterMessage, MasterThreadMessage, ThreadId, UiThreadMessage};
+use crate::{
+    GameThreadMessage, MasterMessage, MasterThreadMessage, Model, ThreadId, UiThreadMessage,
+};
 use actor_plot_remote::ActorPlotRemote;
 use actor_schedule::ActorSchedule;
 use crossbeam_channel::{Receiver, Sender};
-use env::{Env, StepError};
+use env::{Env, EnvBatch, StepError};
+use file_io::Manifest;
 use image::ImageOwned2;
 use model::traits::{Actor, Persistable, TargetNet};
-use model::BasicModel;
 use packets::ActorSettings;
 use param_updater_thread::{spawn_param_updater_thread, ParamUpdaterThreadMessage};
 use rand::Rng;
@@ -23,46 +25,91 @@ use state_accums::{FrameStack, PipeFilterToAccum};
 use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
 use std::thread::JoinHandle;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 type Accum = PipeFilterToAccum<CompressFilter, FrameStack<<CompressFilter as Filter>::Output>>;
 type ConcreteEnv = Env<Accum>;
+type ConcreteEnvBatch = EnvBatch<Accum>;
 
 fn random_action() -> u8 {
     rand::thread_rng().gen_range(0..ConcreteEnv::n_actions())
 }
 
+// picks a random action for each state with probability eps, and otherwise
+// the best one. The states that need the model are passed to it in a single
+// batch, unless there is only one, which best_action takes on its own even if
+// the model can't compute q values in batches
+fn choose_actions<State>(
+    states: &[State],
+    agent: &Arc<RwLock<RemoteReplayWrapper<Model>>>,
+    eps: f64,
+) -> Vec<u8>
+where
+    Model: Actor<State>,
+{
+    let mut rng = rand::thread_rng();
+    let explores = states
+        .iter()
+        .map(|_| rng.gen::<f64>() < eps)
+        .collect::<Vec<_>>();
+    let greedy_states = states
+        .iter()
+        .zip(&explores)
+        .filter(|(_, explores)| !**explores)
+        .map(|(state, _)| state)
+        .collect::<Vec<_>>();
+    let mut greedy_actions = match greedy_states[..] {
+        [] => vec![],
+        [state] => vec![agent.read().unwrap().best_action(state)],
+        _ => {
+            let agent = agent.read().unwrap();
+            agent
+                .q_vals_batch(&greedy_states)
+                .iter()
+                .map(|q_vals| model::argmax(q_vals) as u8)
+                .collect()
+        }
+    }
+    .into_iter();
+    explores
+        .into_iter()
+        .map(|explores| {
+            if explores {
+                random_action()
+            } else {
+                greedy_actions.next().unwrap()
+            }
+        })
+        .collect()
+}
+
 const THREAD_ID: ThreadId = ThreadId::Env;
 const THREAD_NAME: &str = "env";
 
 fn step(
-    env: &mut ConcreteEnv,
-    agent: &Arc<RwLock<RemoteReplayWrapper<BasicModel>>>,
+    envs: &mut ConcreteEnvBatch,
+    agent: &Arc<RwLock<RemoteReplayWrapper<Model>>>,
     schedule: &mut ActorSchedule,
     master_thread_sender: &Sender<MasterThreadMessage>,
     ui_thread_sender: &Sender<UiThreadMessage>,
     plot_remote: &mut Option<ActorPlotRemote>,
     param_updater_thread_sender: &Sender<ParamUpdaterThreadMessage>,
 ) -> bool {
-    let state = env.state();
-    let concated_state = State::concat_frames(&(&state).into());
+    let states = envs.states();
+    let concated_state = State::concat_frames(&(&envs.shown_state()).into());
     ui_thread_sender
         .send(UiThreadMessage::Frame(concated_state))
         .unwrap();
     ui_thread_sender
         .send(UiThreadMessage::NStep(schedule.n_step()))
         .unwrap();
-    let action = if rand::thread_rng().gen::<f64>() < schedule.eps() {
-        random_action()
-    } else {
-        let agent = agent.read().unwrap();
-        agent.best_action(&state)
-    };
+    let actions = choose_actions(&states, agent, schedule.eps());
     // the env thread needs to handle hold requests carefully
     // the purpose of this variable is to ensure that the env thread
     // obeys hold requests right at the end of the frame where they
     // were produced
     let mut should_hold = false;
-    match env.step(action) {
+    match envs.step(&actions) {
         Ok(()) => {}
         Err(StepError::WaitForHoldRequest) => {
             master_thread_sender
@@ -72,14 +119,14 @@ fn step(
         }
         Err(StepError::BadMessage) => panic!("{THREAD_NAME} thread: bad message"),
     };
-    while let Some((transition, episode_score)) = env.pop_transition() {
+    while let Some((transition, episode_score, metadata)) = envs.pop_transition() {
         if let Some(score) = episode_score {
             if let Some(ref mut plot_remote) = plot_remote {
                 plot_remote.send(score);
             }
         }
         let mut agent = agent.write().unwrap();
-        agent.remember(transition);
+        agent.remember(transition, Some(metadata));
     }
     if schedule.is_time_to_update_params() {
         param_updater_thread_sender
@@ -116,13 +163,14 @@ fn communicate_hold_sequence(
 }
 
 pub enum EnvThreadMessage {
-    Frame((ImageOwned2, u32)),
+    // a frame of the game of the env at the index, along with its score
+    Frame(usize, (ImageOwned2, u32)),
     Master(MasterMessage),
     WaitForHold,
 }
 
 enum ThreadMode {
-    Running(ConcreteEnv),
+    Running(ConcreteEnvBatch),
     Held,
 }
 
@@ -133,22 +181,44 @@ impl ThreadType for EnvThread {
     type SpawnArgs = (
         Sender<MasterThreadMessage>,
         Sender<UiThreadMessage>,
-        Sender<GameThreadMessage>,
+        Vec<Sender<GameThreadMessage>>,
         crate::Args,
         ActorSettings,
     );
 
     fn spawn(receiver: Receiver<Self::Message>, args: Self::SpawnArgs) -> JoinHandle<()> {
         std::thread::spawn(move || {
-            let (master_thread_sender, ui_thread_sender, game_thread_sender, cmd_args, settings) =
+            let (master_thread_sender, ui_thread_sender, game_thread_senders, cmd_args, settings) =
                 args;
             const PARAM_UPDATE_INTERVAL_STEPS: u32 = 400;
-            const ALPHA: f64 = 0.6;
-            let agent = RemoteReplayWrapper::wrap(
-                BasicModel::new(cmd_args.model_def_path),
-                settings.replay_server_addr,
-                ALPHA,
+            let model = match Model::for_inference(&cmd_args.model_def_path) {
+                Ok(model) => model,
+                Err(e) => panic!(
+                    "{THREAD_NAME} thread: could not load model {}: {}",
+                    cmd_args.model_def_path.display(),
+                    e
+                ),
+            };
+            // the model must have been created for the same env that this thread
+            // runs
+            model.metadata().check_env(
+                ConcreteEnv::n_actions(),
+                ConcreteEnv::n_steps(),
+                ConcreteEnv::gamma(),
+            );
+            // the envs stack as many frames as the model takes
+            let frame_stack_len = model.state_shape().frame_stack_len();
+            assert!(
+                cmd_args.skip_initial_priorities || model.can_compute_abs_td_errors(),
+                "the model can't compute initial priorities, so they must be skipped"
             );
+            assert!(
+                cmd_args.env_count <= 1 || model.can_compute_q_vals_batch(),
+                "the model can't compute q values in batches, so it can only run a single env. \
+                 Re-exporting the model with create_model.py adds q_vals_batch"
+            );
+            let mut agent = RemoteReplayWrapper::wrap(model, settings.replay_server_addr);
+            agent.set_computes_initial_priorities(!cmd_args.skip_initial_priorities);
             let agent = Arc::new(RwLock::new(agent));
             let (param_updater_thread_sender, param_updater_thread_receiver) =
                 crossbeam_channel::unbounded::<ParamUpdaterThreadMessage>();
@@ -162,25 +232,55 @@ impl ThreadType for EnvThread {
                 .plot_server_addr
                 .map(|addr| ActorPlotRemote::new(addr, settings.id, 10));
             let mut mode = ThreadMode::Held;
+            // the ids start from the time the actor started, in microseconds,
+            // so that they don't collide with the ids from its earlier runs,
+            // whose transitions may still be in the replay server
+            let mut next_episode_id = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_micros() as u64;
             loop {
                 match mode {
                     ThreadMode::Held => match receiver.recv().unwrap() {
                         EnvThreadMessage::Master(message) => match message {
                             MasterMessage::Save(path) => {
-                                schedule.save(path.as_path());
+                                let mut manifest = Manifest::new();
+                                manifest.add_config(
+                                    "model_def_path",
+                                    cmd_args.model_def_path.display(),
+                                );
+                                manifest.add_config("actor_id", settings.id);
+                                manifest.add_config("env_count", cmd_args.env_count);
+                                schedule.save(path.as_path(), &mut manifest);
                                 {
                                     let agent = agent.read().unwrap();
-                                    agent.save(path);
+                                    agent.save(path.as_path());
                                 }
+                                manifest.save(path).unwrap();
                                 master_thread_sender
                                     .send(MasterThreadMessage::Done(THREAD_ID))
                                     .unwrap();
                             }
                             MasterMessage::Load(path) => {
-                                schedule.load(path.as_path());
-                                {
-                                    let mut agent = agent.write().unwrap();
-                                    agent.load(path);
+                                // the schedule is only replaced once the agent has
+                                // loaded too, so that a failed load keeps all of
+                                // the current state
+                                let load_result = Manifest::load(path.as_path())
+                                    .and_then(|manifest| {
+                                        ActorSchedule::from_saved(path.as_path(), &manifest)
+                                    })
+                                    .and_then(|loaded_schedule| {
+                                        let mut agent = agent.write().unwrap();
+                                        agent.load(path.as_path())?;
+                                        Ok(loaded_schedule)
+                                    });
+                                match load_result {
+                                    Ok(loaded_schedule) => schedule = loaded_schedule,
+                                    Err(e) => eprintln!(
+                                        "{THREAD_NAME} thread: could not load {}: {}",
+                                        path.display(),
+                                        e
+                                    ),
                                 }
                                 master_thread_sender
                                     .send(MasterThreadMessage::Done(THREAD_ID))
@@ -190,9 +290,15 @@ impl ThreadType for EnvThread {
                                 eprintln!("{THREAD_NAME} thread: {:?} while already held", message);
                             }
                             MasterMessage::Resume => {
-                                match Env::new(receiver.clone(), game_thread_sender.clone()) {
-                                    Ok(env) => {
-                                        mode = ThreadMode::Running(env);
+                                match EnvBatch::new(
+                                    receiver.clone(),
+                                    game_thread_senders.clone(),
+                                    settings.id,
+                                    next_episode_id,
+                                    frame_stack_len,
+                                ) {
+                                    Ok(envs) => {
+                                        mode = ThreadMode::Running(envs);
                                     }
                                     Err(StepError::WaitForHoldRequest) => {
                                         master_thread_sender
@@ -211,9 +317,9 @@ impl ThreadType for EnvThread {
                         },
                         _ => panic!("{THREAD_NAME} thread: bad message"),
                     },
-                    ThreadMode::Running(ref mut env) => {
+                    ThreadMode::Running(ref mut envs) => {
                         let should_hold = step(
-                            env,
+                            envs,
                             &agent,
                             &mut schedule,
                             &master_thread_sender,
@@ -222,6 +328,7 @@ impl ThreadType for EnvThread {
                             &param_updater_thread_sender,
                         );
                         if should_hold {
+                            next_episode_id = envs.next_episode_id();
                             mode = ThreadMode::Held;
                             communicate_hold_sequence(&receiver, &master_thread_sender);
                         }
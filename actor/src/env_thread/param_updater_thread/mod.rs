@@ -1,9 +1,9 @@
 mod learner_client;
 
+use crate::Model;
 use crossbeam_channel::Receiver;
 use learner_client::LearnerClient;
 use model::traits::ParamFetcher;
-use model::BasicModel;
 use packets::ActorSettings;
 use replay_wrappers::RemoteReplayWrapper;
 use std::sync::{Arc, RwLock};
@@ -16,7 +16,7 @@ pub enum ParamUpdaterThreadMessage {
 
 pub fn spawn_param_updater_thread(
     receiver: Receiver<ParamUpdaterThreadMessage>,
-    agent: Arc<RwLock<RemoteReplayWrapper<BasicModel>>>,
+    agent: Arc<RwLock<RemoteReplayWrapper<Model>>>,
     settings: &ActorSettings,
 ) -> JoinHandle<()> {
     let learner_client = settings.learner_addr.map(LearnerClient::new);
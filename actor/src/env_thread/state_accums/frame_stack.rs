@@ -3,6 +3,7 @@ use replay_data::GenericState;
 use std::collections::VecDeque;
 use std::fmt::Debug;
 
+// Holds the last frames, as many as the len it was created with
 #[derive(Clone)]
 pub struct FrameStack<Frame> {
     stack: VecDeque<Frame>,
@@ -14,6 +15,8 @@ where
 {
     type Frame = Frame;
     type View = GenericState<Frame>;
+    // the len of the stack
+    type Settings = usize;
 
     fn receive(&mut self, frame: Self::Frame) {
         self.stack.pop_front();
@@ -21,18 +24,18 @@ where
     }
 
     fn view(&self) -> Self::View {
-        <[Frame; 4]>::try_from(Vec::from(self.stack.clone()))
-            .expect("frame stack len should be 4")
-            .into()
+        Vec::from(self.stack.clone()).into()
     }
     fn reset_to_current(&mut self) {
+        let len = self.stack.len();
         let frame = self.stack.pop_back().unwrap();
-        *self = Self::from_frame(frame);
+        *self = Self::from_frame(frame, len);
     }
 
-    fn from_frame(frame: Self::Frame) -> Self {
+    fn from_frame(frame: Self::Frame, len: usize) -> Self {
+        assert!(len > 0, "frame stack len should be positive");
         Self {
-            stack: VecDeque::from([frame.clone(), frame.clone(), frame.clone(), frame]),
+            stack: std::iter::repeat_n(frame, len).collect(),
         }
     }
 }
@@ -15,6 +15,7 @@ where
 {
     type Frame = <F as Filter>::Input;
     type View = <A as StateAccum>::View;
+    type Settings = <A as StateAccum>::Settings;
 
     fn receive(&mut self, frame: Self::Frame) {
         self.accum.receive(F::call(frame));
@@ -27,9 +28,9 @@ where
         self.accum.reset_to_current()
     }
 
-    fn from_frame(frame: Self::Frame) -> Self {
+    fn from_frame(frame: Self::Frame, settings: Self::Settings) -> Self {
         Self {
-            accum: A::from_frame(F::call(frame)),
+            accum: A::from_frame(F::call(frame), settings),
             _marker: PhantomData,
         }
     }
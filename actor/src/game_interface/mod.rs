@@ -80,6 +80,37 @@ fn find_descendant_win_with_pid(conn: &XCBConnection, parent: u32, pid: u32) ->
     find_descendant_win(conn, parent, &mut |win| win_pid_eq(conn, win, pid))
 }
 
+// Whether a compositing manager runs on the default screen. Without one, the
+// contents of the parts of a window that other windows cover are undefined,
+// so the frames grabbed from overlapping game windows can't be trusted. A
+// compositing manager keeps every window in an offscreen buffer of its own
+pub fn screen_is_composited() -> bool {
+    let mut display = X11Display::open().expect("Can't open a connection to the X server");
+    let screen_num = display.default_screen();
+    let composited = {
+        let conn = display
+            .to_xcb_connection_mut()
+            .expect("Can't convert display to xcb connection");
+        // the manager of each screen owns this selection, as per the EWMH spec
+        let selection_name = format!("_NET_WM_CM_S{screen_num}");
+        let selection = conn
+            .intern_atom(false, selection_name.as_bytes())
+            .unwrap()
+            .reply()
+            .unwrap()
+            .atom;
+        let owner = conn
+            .get_selection_owner(selection)
+            .unwrap()
+            .reply()
+            .unwrap()
+            .owner;
+        owner != x11rb::NONE
+    };
+    display.close();
+    composited
+}
+
 #[derive(Clone, Copy)]
 pub enum GameKey {
     S,
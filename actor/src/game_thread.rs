@@ -69,11 +69,12 @@ impl ThreadType for GameThread {
         Sender<MasterThreadMessage>,
         Sender<UiThreadMessage>,
         Sender<EnvThreadMessage>,
+        usize,
     );
 
     fn spawn(receiver: Receiver<GameThreadMessage>, args: Self::SpawnArgs) -> JoinHandle<()> {
         std::thread::spawn(move || {
-            let (master_thread_sender, ui_thread_sender, env_thread_sender) = args;
+            let (master_thread_sender, ui_thread_sender, env_thread_sender, env_index) = args;
             let mut mode = ThreadMode::Held;
             let mut game = Game::new();
             loop {
@@ -95,10 +96,13 @@ impl ThreadType for GameThread {
                             }
                             MasterMessage::Resume => {
                                 game.start();
-                                let win = game.interface().win().clone();
-                                ui_thread_sender
-                                    .send(UiThreadMessage::WinDims(win))
-                                    .unwrap();
+                                // the ui only shows the first game
+                                if env_index == 0 {
+                                    let win = game.interface().win().clone();
+                                    ui_thread_sender
+                                        .send(UiThreadMessage::WinDims(win))
+                                        .unwrap();
+                                }
                                 let current_start_time = std::time::Instant::now();
                                 mode = ThreadMode::Running(current_start_time);
                             }
@@ -113,7 +117,10 @@ impl ThreadType for GameThread {
                         let preprocessed_frame = preprocess_frame(&game.get_current_frame());
                         let score = game.get_current_score();
                         env_thread_sender
-                            .send(EnvThreadMessage::Frame((preprocessed_frame, score)))
+                            .send(EnvThreadMessage::Frame(
+                                env_index,
+                                (preprocessed_frame, score),
+                            ))
                             .unwrap();
 
                         match receiver.recv().unwrap() {
@@ -15,7 +15,7 @@ use clap::Parser;
 use coordinator_client::CoordinatorClient;
 use env_thread::{EnvThread, EnvThreadMessage};
 use game::Game;
-use game_interface::{GameInterface, GameKey, KeyEventKind};
+use game_interface::{screen_is_composited, GameInterface, GameKey, KeyEventKind};
 use game_thread::{GameThread, GameThreadMessage};
 use human_interface::HumanInterface;
 use master_thread::{spawn_master_thread, MasterMessage, MasterThreadMessage, ThreadId};
@@ -23,6 +23,14 @@ use prompt::prompt_user_for_service_ip_addr;
 use ui_thread::{UiThread, UiThreadMessage};
 use x11_utils::{choose_matching_fbconfigs, GlxContext, Window, X11Display};
 
+// the model backend, chosen by the crate's features
+#[cfg(feature = "tensorflow")]
+type Model = model::BasicModel;
+#[cfg(all(feature = "reference", not(feature = "tensorflow")))]
+type Model = model::ReferenceModel;
+#[cfg(not(any(feature = "tensorflow", feature = "reference")))]
+compile_error!("a model backend must be enabled with the tensorflow or reference feature");
+
 // # Rationale for enabling jemalloc
 //
 // The program is very demanding in terms of RAM usage, typically requiring
@@ -2,17 +2,20 @@ mod message;
 mod thread_id;
 
 use super::thread::{ActiveThread, Thread};
-use crate::{EnvThread, GameThread, UiThread};
+use crate::{screen_is_composited, EnvThread, GameThread, UiThread};
 use crate::{EnvThreadMessage, GameThreadMessage, UiThreadMessage};
 use crossbeam_channel::Receiver;
+use file_io::{checkpoint, LoadError};
 pub use message::{MasterMessage, MasterThreadMessage};
 use packets::ActorSettings;
-use std::fs;
+use std::io;
 use std::path::Path;
 pub use thread_id::ThreadId;
 
 pub enum CommandError {
     ModeMatch,
+    Save(io::Error),
+    Load(LoadError),
 }
 
 #[derive(Copy, Clone)]
@@ -26,33 +29,48 @@ pub struct Master {
     receiver: Receiver<MasterThreadMessage>,
     ui_thread: ActiveThread<UiThread>,
     env_thread: ActiveThread<EnvThread>,
-    game_thread: ActiveThread<GameThread>,
+    // one for each env
+    game_threads: Vec<ActiveThread<GameThread>>,
 }
 
 impl Master {
     pub fn new(args: crate::Args, settings: ActorSettings) -> Self {
+        // the games all open a full-size window on the same display, so with
+        // more than one env their windows cover each other
+        assert!(
+            args.env_count == 1 || screen_is_composited(),
+            "running more than one env needs a compositing manager on the X display, \
+             since the frames of covered game windows are undefined without one"
+        );
         let (sender, receiver) = crossbeam_channel::unbounded::<MasterThreadMessage>();
-        let game_thread = Thread::new();
         let ui_thread = Thread::new();
         let env_thread = Thread::new();
 
-        let game_thread = game_thread.spawn((
-            sender.clone(),
-            ui_thread.sender().clone(),
-            env_thread.sender().clone(),
-        ));
+        let game_threads = (0..args.env_count as usize)
+            .map(|env_index| {
+                Thread::new().spawn((
+                    sender.clone(),
+                    ui_thread.sender().clone(),
+                    env_thread.sender().clone(),
+                    env_index,
+                ))
+            })
+            .collect::<Vec<ActiveThread<GameThread>>>();
         let ui_thread = ui_thread.spawn(sender.clone());
         let env_thread = env_thread.spawn((
             sender,
             ui_thread.sender().clone(),
-            game_thread.sender().clone(),
+            game_threads
+                .iter()
+                .map(|game_thread| game_thread.sender().clone())
+                .collect(),
             args,
             settings,
         ));
         Self {
             mode: Mode::Held,
             receiver,
-            game_thread,
+            game_threads,
             ui_thread,
             env_thread,
         }
@@ -65,19 +83,19 @@ impl Master {
     fn send_all(&self, message: MasterMessage) {
         self.ui_thread.send_master(message.clone()).unwrap();
         self.env_thread.send_master(message.clone()).unwrap();
-        self.game_thread.send_master(message).unwrap();
+        for game_thread in &self.game_threads {
+            game_thread.send_master(message.clone()).unwrap();
+        }
     }
 
     fn wait_all_done(&self) {
-        let mut ready_thread_flags = 0;
-        loop {
+        // every thread reports once, and there may be several game threads
+        let mut pending_flags = ThreadId::Ui.as_bit_flag() | ThreadId::Env.as_bit_flag();
+        let mut pending_game_count = self.game_threads.len();
+        while pending_flags != 0 || pending_game_count != 0 {
             match self.receiver.recv().unwrap() {
-                MasterThreadMessage::Done(thread_id) => {
-                    ready_thread_flags |= thread_id.as_bit_flag();
-                    if ready_thread_flags == ThreadId::all_flags() {
-                        break;
-                    }
-                }
+                MasterThreadMessage::Done(ThreadId::Game) => pending_game_count -= 1,
+                MasterThreadMessage::Done(thread_id) => pending_flags &= !thread_id.as_bit_flag(),
             }
         }
     }
@@ -94,10 +112,12 @@ impl Master {
         match self.mode {
             Mode::Running => Err(CommandError::ModeMatch),
             Mode::Held => {
-                fs::create_dir_all(path).unwrap();
-                self.send_all(MasterMessage::Save(path.into()));
+                // the threads save into a staging directory, which replaces
+                // the checkpoint at path only once all of them are done
+                let staging_path = checkpoint::begin(path).map_err(CommandError::Save)?;
+                self.send_all(MasterMessage::Save(staging_path));
                 self.wait_all_done();
-                Ok(())
+                checkpoint::commit(path).map_err(CommandError::Save)
             }
         }
     }
@@ -107,6 +127,7 @@ impl Master {
         match self.mode {
             Mode::Running => Err(CommandError::ModeMatch),
             Mode::Held => {
+                checkpoint::verify(path).map_err(CommandError::Load)?;
                 self.send_all(MasterMessage::Load(path.into()));
                 self.wait_all_done();
                 Ok(())
@@ -148,7 +169,9 @@ impl Master {
                 self.send_all(MasterMessage::Close);
                 self.ui_thread.join().unwrap();
                 self.env_thread.join().unwrap();
-                self.game_thread.join().unwrap();
+                for game_thread in self.game_threads {
+                    game_thread.join().unwrap();
+                }
                 Ok(())
             }
         }
@@ -12,7 +12,4 @@ impl ThreadId {
             Self::Env => 4,
         }
     }
-    pub fn all_flags() -> u8 {
-        Self::Game.as_bit_flag() | Self::Ui.as_bit_flag() | Self::Env.as_bit_flag()
-    }
 }
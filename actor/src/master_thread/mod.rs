@@ -15,6 +15,14 @@ fn print_mode_match_err(mode: Mode) {
     eprintln!("command cannot be executed in {} mode", mode)
 }
 
+fn print_command_err(e: CommandError, mode: Mode) {
+    match e {
+        CommandError::ModeMatch => print_mode_match_err(mode),
+        CommandError::Save(e) => eprintln!("could not save: {}", e),
+        CommandError::Load(e) => eprintln!("could not load: {}", e),
+    }
+}
+
 pub fn spawn_master_thread(args: crate::Args, settings: ActorSettings) -> JoinHandle<()> {
     std::thread::spawn(move || {
         const THREAD_NAME: &str = "master";
@@ -23,6 +31,7 @@ pub fn spawn_master_thread(args: crate::Args, settings: ActorSettings) -> JoinHa
         if settings.activate {
             master.resume().unwrap_or_else(|e| match e {
                 CommandError::ModeMatch => unreachable!("the master should start in Mode::Held"),
+                _ => unreachable!("resuming should only fail on a mode mismatch"),
             });
         }
 
@@ -31,23 +40,23 @@ pub fn spawn_master_thread(args: crate::Args, settings: ActorSettings) -> JoinHa
             std::io::stdin().read_line(&mut command).unwrap();
             let command = command.split_whitespace().collect::<Vec<_>>();
             match command[..] {
-                ["save", path] => master.save(path).unwrap_or_else(|e| match e {
-                    CommandError::ModeMatch => print_mode_match_err(master.mode()),
-                }),
-                ["load", path] => master.load(path).unwrap_or_else(|e| match e {
-                    CommandError::ModeMatch => print_mode_match_err(master.mode()),
-                }),
-                ["hold"] => master.hold().unwrap_or_else(|e| match e {
-                    CommandError::ModeMatch => print_mode_match_err(master.mode()),
-                }),
-                ["resume"] => master.resume().unwrap_or_else(|e| match e {
-                    CommandError::ModeMatch => print_mode_match_err(master.mode()),
-                }),
+                ["save", path] => master
+                    .save(path)
+                    .unwrap_or_else(|e| print_command_err(e, master.mode())),
+                ["load", path] => master
+                    .load(path)
+                    .unwrap_or_else(|e| print_command_err(e, master.mode())),
+                ["hold"] => master
+                    .hold()
+                    .unwrap_or_else(|e| print_command_err(e, master.mode())),
+                ["resume"] => master
+                    .resume()
+                    .unwrap_or_else(|e| print_command_err(e, master.mode())),
                 ["close"] => match master.close() {
                     Ok(()) => break,
-                    Err((master_return, CommandError::ModeMatch)) => {
+                    Err((master_return, e)) => {
                         master = master_return;
-                        print_mode_match_err(master.mode());
+                        print_command_err(e, master.mode());
                     }
                 },
                 _ => {
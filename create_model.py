@@ -1,9 +1,27 @@
+import argparse
+import json
 import tensorrt
 import tensorflow as tf
 from tensorflow import keras
 
+# each frame takes up two channels of a state
+CHANNELS_PER_FRAME = 2
+
+parser = argparse.ArgumentParser(description="Creates the model as a SavedModel in ./model")
+parser.add_argument("--frame-stack-len", type=int, default=4, help="the number of frames in a state")
+parser.add_argument("--frame-height", type=int, default=72)
+parser.add_argument("--frame-width", type=int, default=128)
+parser.add_argument("--n-steps", type=int, default=3, help="the number of steps that targets bootstrap over")
+parser.add_argument("--gamma", type=float, default=0.99)
+parser.add_argument("--no-jump", action="store_true", help="leaves out the jump action")
+args = parser.parse_args()
+
+# the programs read the state shape back from the signatures of the model, so
+# this is the only place that it needs to be changed
+STATE_SHAPE = [CHANNELS_PER_FRAME * args.frame_stack_len, args.frame_height, args.frame_width]
+
 def transition_inputs():
-    return keras.layers.Input(shape=(8, 72, 128))
+    return keras.layers.Input(shape=STATE_SHAPE)
 
 def preprocessed_inputs(inputs):
     normalized = keras.layers.Lambda(lambda x : x / 255.0)(inputs)
@@ -57,10 +75,10 @@ GRAD_NORM_CLIPPING = 10
 DUELING_GRAD_WEIGHTING = False
 
 LEARNING_RATE = 0.000025
-N_STEPS = 3
-GAMMA = 0.99
+N_STEPS = args.n_steps
+GAMMA = args.gamma
 
-JUMP = True
+JUMP = not args.no_jump
 
 if JUMP:
     n_actions = 3
@@ -171,6 +189,9 @@ class Agent(tf.Module):
         
         return qvals
     @tf.function
+    def predict_qvals_batch(self, states):
+        return self.control_model(states)
+    @tf.function
     def best_action(self, state):
         return tf.argmax(self.predict_qvals(state))
     @tf.function
@@ -251,9 +272,9 @@ class Agent(tf.Module):
         
 agent = Agent()
 
-single_state = tf.TensorSpec([8, 72, 128], dtype=tf.uint8)
-states = tf.TensorSpec([None, 8, 72, 128], dtype=tf.uint8)
-next_states = tf.TensorSpec([None, 8, 72, 128], dtype=tf.uint8)
+single_state = tf.TensorSpec(STATE_SHAPE, dtype=tf.uint8)
+states = tf.TensorSpec([None] + STATE_SHAPE, dtype=tf.uint8)
+next_states = tf.TensorSpec([None] + STATE_SHAPE, dtype=tf.uint8)
 actions = tf.TensorSpec([None], dtype=tf.uint8)
 rewards = tf.TensorSpec([None])
 dones = tf.TensorSpec([None], dtype=tf.float32)
@@ -264,6 +285,8 @@ beta = tf.TensorSpec([], dtype=tf.float32)
 path = tf.TensorSpec([], dtype=tf.string)
 
 best_action = agent.best_action.get_concrete_function(single_state)
+q_vals = agent.predict_qvals.get_concrete_function(single_state)
+q_vals_batch = agent.predict_qvals_batch.get_concrete_function(states)
 train_pred_step = agent.train_pred_step.get_concrete_function(states, next_states, actions, rewards, dones)
 train_pred_step_prioritized = agent.train_pred_step_prioritized.get_concrete_function(states, next_states, actions, rewards, dones, probabilities, min_probability, replay_memory_len, beta)
 compute_abs_td_errors = agent.compute_abs_td_errors.get_concrete_function(states, next_states, actions, rewards, dones)
@@ -276,6 +299,8 @@ set_params = agent.set_params.get_concrete_function(params)
 
 signatures = {
     "best_action": best_action,
+    "q_vals": q_vals,
+    "q_vals_batch": q_vals_batch,
     "train_pred_step": train_pred_step,
     "train_pred_step_prioritized": train_pred_step_prioritized,
     "compute_abs_td_errors": compute_abs_td_errors,
@@ -287,3 +312,13 @@ signatures = {
 }
 
 tf.saved_model.save(agent, export_dir="model", signatures=signatures)
+
+# the programs check that they agree with these before using the model
+metadata = {
+    "n_actions": n_actions,
+    "n_steps": N_STEPS,
+    "gamma": GAMMA,
+    "state_shape": STATE_SHAPE,
+}
+with open("model/metadata.json", "w") as metadata_file:
+    json.dump(metadata, metadata_file, indent=4)
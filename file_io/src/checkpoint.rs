@@ -0,0 +1,228 @@
+// Checkpoints are written into a hidden staging directory next to their target
+// and are only moved into place once they are complete. That way, a crash in
+// the middle of a save neither leaves a half-written checkpoint behind nor
+// destroys the previous one. A checksum of every file is recorded when the
+// checkpoint is committed, and is verified before the checkpoint is loaded
+//
+// Linux can't atomically replace a non-empty directory, so committing first
+// moves the previous checkpoint aside. If a crash happens between the two
+// renames, the previous checkpoint is moved back on the next save or load
+//
+// A staged checkpoint may hard-link unchanged files of the committed one
+// instead of rewriting them, so files of a committed checkpoint must never be
+// modified in place
+use crate::{create_file_buf_write, open_file_buf_read, LoadError};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+
+const CHECKSUMS_FILE_NAME: &str = "checksums.json";
+
+fn sibling_path(target: &Path, suffix: &str) -> PathBuf {
+    let name = target
+        .file_name()
+        .expect("checkpoint path must end with a directory name");
+    let mut sibling_name = OsString::from(".");
+    sibling_name.push(name);
+    sibling_name.push(suffix);
+    target.with_file_name(sibling_name)
+}
+
+pub fn staging_path<P: AsRef<Path>>(target: P) -> PathBuf {
+    sibling_path(target.as_ref(), ".staging")
+}
+
+// maps a path inside of a staging directory to the corresponding path inside of
+// the checkpoint that the staging directory will be committed to. Returns None
+// if the path isn't inside of a staging directory
+pub fn committed_path<P: AsRef<Path>>(path: P) -> Option<PathBuf> {
+    let mut committed_path = PathBuf::new();
+    let mut found_staging = false;
+    for component in path.as_ref().components() {
+        let target_name = match component {
+            Component::Normal(name) if !found_staging => name
+                .to_str()
+                .and_then(|name| name.strip_prefix('.')?.strip_suffix(".staging")),
+            _ => None,
+        };
+        match target_name {
+            Some(target_name) => {
+                committed_path.push(target_name);
+                found_staging = true;
+            }
+            None => committed_path.push(component),
+        }
+    }
+    found_staging.then_some(committed_path)
+}
+
+fn old_path(target: &Path) -> PathBuf {
+    sibling_path(target, ".old")
+}
+
+fn parent_path(target: &Path) -> &Path {
+    match target.parent() {
+        Some(parent) if parent != Path::new("") => parent,
+        _ => Path::new("."),
+    }
+}
+
+// prepares an empty staging directory for a checkpoint that will eventually be
+// committed to target, and returns its path
+pub fn begin<P: AsRef<Path>>(target: P) -> io::Result<PathBuf> {
+    let target = target.as_ref();
+    recover(target)?;
+    let staging_path = staging_path(target);
+    // a staging directory may be left over from an interrupted save
+    if staging_path.exists() {
+        fs::remove_dir_all(&staging_path)?;
+    }
+    fs::create_dir_all(&staging_path)?;
+    Ok(staging_path)
+}
+
+// records checksums for the staged checkpoint, flushes it to disk, and moves it
+// to target, replacing whatever checkpoint target held before
+pub fn commit<P: AsRef<Path>>(target: P) -> io::Result<()> {
+    let target = target.as_ref();
+    let staging_path = staging_path(target);
+    let old_path = old_path(target);
+    write_checksums(&staging_path, target)?;
+    sync_all(&staging_path)?;
+    if target.exists() {
+        if old_path.exists() {
+            fs::remove_dir_all(&old_path)?;
+        }
+        fs::rename(target, &old_path)?;
+    }
+    fs::rename(&staging_path, target)?;
+    File::open(parent_path(target))?.sync_all()?;
+    if old_path.exists() {
+        fs::remove_dir_all(&old_path)?;
+    }
+    Ok(())
+}
+
+// moves the previous checkpoint back into place if a commit was interrupted
+// right after moving it aside
+pub fn recover<P: AsRef<Path>>(target: P) -> io::Result<()> {
+    let target = target.as_ref();
+    let old_path = old_path(target);
+    if !target.exists() && old_path.exists() {
+        fs::rename(&old_path, target)?;
+    }
+    Ok(())
+}
+
+// checks that the files of the checkpoint at target match the checksums that
+// were recorded on commit
+pub fn verify<P: AsRef<Path>>(target: P) -> Result<(), LoadError> {
+    let target = target.as_ref();
+    recover(target).map_err(|e| LoadError::Io(target.into(), e))?;
+    let checksums_path = target.join(CHECKSUMS_FILE_NAME);
+    let checksums_file = open_file_buf_read(&checksums_path)
+        .map_err(|e| LoadError::Io(checksums_path.clone(), e))?;
+    let checksums: BTreeMap<String, u32> = serde_json::from_reader(checksums_file)
+        .map_err(|e| LoadError::JsonDecode(checksums_path, e))?;
+    for (relative_path, expected_checksum) in checksums {
+        let path = target.join(relative_path);
+        let checksum = checksum(&path).map_err(|e| LoadError::Io(path.clone(), e))?;
+        if checksum != expected_checksum {
+            return Err(LoadError::Checksum(path));
+        }
+    }
+    Ok(())
+}
+
+pub fn checksum<P: AsRef<Path>>(path: P) -> io::Result<u32> {
+    let mut file = File::open(path)?;
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buf = vec![0; 1 << 16];
+    loop {
+        let read_len = file.read(&mut buf)?;
+        if read_len == 0 {
+            return Ok(hasher.finalize());
+        }
+        hasher.update(&buf[..read_len]);
+    }
+}
+
+// lists the files under dir recursively, as paths relative to dir
+fn files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    let mut dirs = vec![PathBuf::new()];
+    while let Some(relative_dir) = dirs.pop() {
+        for entry in fs::read_dir(dir.join(&relative_dir))? {
+            let entry = entry?;
+            let relative_path = relative_dir.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                dirs.push(relative_path);
+            } else {
+                files.push(relative_path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn load_checksums(dir: &Path) -> Option<BTreeMap<String, u32>> {
+    let file = open_file_buf_read(dir.join(CHECKSUMS_FILE_NAME)).ok()?;
+    serde_json::from_reader(file).ok()
+}
+
+#[cfg(unix)]
+fn is_same_file(path_a: &Path, path_b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (fs::metadata(path_a), fs::metadata(path_b)) {
+        (Ok(metadata_a), Ok(metadata_b)) => {
+            metadata_a.dev() == metadata_b.dev() && metadata_a.ino() == metadata_b.ino()
+        }
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn is_same_file(_path_a: &Path, _path_b: &Path) -> bool {
+    false
+}
+
+// files that the staged checkpoint hard-links from the committed one keep
+// their recorded checksums, so that they don't have to be read again
+fn write_checksums(dir: &Path, target: &Path) -> io::Result<()> {
+    let target_checksums = load_checksums(target).unwrap_or_default();
+    let mut checksums = BTreeMap::new();
+    for relative_path in files(dir)? {
+        let path = dir.join(&relative_path);
+        let relative_path = relative_path.to_string_lossy().into_owned();
+        let target_checksum = target_checksums
+            .get(&relative_path)
+            .filter(|_| is_same_file(&path, &target.join(&relative_path)));
+        let checksum = match target_checksum {
+            Some(&checksum) => checksum,
+            None => checksum(&path)?,
+        };
+        checksums.insert(relative_path, checksum);
+    }
+    let file = create_file_buf_write(dir.join(CHECKSUMS_FILE_NAME))?;
+    serde_json::to_writer_pretty(file, &checksums)?;
+    Ok(())
+}
+
+fn sync_all(dir: &Path) -> io::Result<()> {
+    for relative_path in files(dir)? {
+        File::open(dir.join(relative_path))?.sync_all()?;
+    }
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                dirs.push(entry.path());
+            }
+        }
+        File::open(&dir)?.sync_all()?;
+    }
+    Ok(())
+}
@@ -1,3 +1,10 @@
+pub mod checkpoint;
+mod load_error;
+mod manifest;
+
+pub use load_error::LoadError;
+pub use manifest::Manifest;
+use serde::de::DeserializeOwned;
 use std::fs::File;
 use std::io;
 use std::io::BufRead;
@@ -19,3 +26,20 @@ pub fn open_file_buf_read<P: AsRef<Path>>(path: P) -> io::Result<BufReader<File>
 pub fn has_data_left<R: BufRead>(mut reader: R) -> io::Result<bool> {
     reader.fill_buf().map(|b| !b.is_empty())
 }
+
+// deserializes a file holding a single bincode value, making sure that nothing
+// follows the value
+pub fn load_bincode<T, P>(path: P) -> Result<T, LoadError>
+where
+    T: DeserializeOwned,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let mut file = open_file_buf_read(path).map_err(|e| LoadError::Io(path.into(), e))?;
+    let value =
+        bincode::deserialize_from(&mut file).map_err(|e| LoadError::Decode(path.into(), e))?;
+    if has_data_left(file).map_err(|e| LoadError::Io(path.into(), e))? {
+        return Err(LoadError::TrailingData(path.into()));
+    }
+    Ok(value)
+}
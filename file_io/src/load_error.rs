@@ -0,0 +1,59 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(PathBuf, io::Error),
+    Decode(PathBuf, bincode::Error),
+    JsonDecode(PathBuf, serde_json::Error),
+    TrailingData(PathBuf),
+    Checksum(PathBuf),
+    FormatVersion {
+        found: u32,
+        supported: u32,
+    },
+    MissingComponent(String),
+    ComponentVersion {
+        component: String,
+        found: u32,
+        supported: u32,
+    },
+    Invalid(String),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(path, e) => write!(f, "could not read {}: {}", path.display(), e),
+            Self::Decode(path, e) => write!(f, "could not decode {}: {}", path.display(), e),
+            Self::JsonDecode(path, e) => {
+                write!(f, "could not decode {}: {}", path.display(), e)
+            }
+            Self::TrailingData(path) => {
+                write!(f, "deserialization of {} didn't reach EOF", path.display())
+            }
+            Self::Checksum(path) => write!(f, "checksum mismatch in {}", path.display()),
+            Self::FormatVersion { found, supported } => write!(
+                f,
+                "unsupported format version {} (supported version is {})",
+                found, supported
+            ),
+            Self::MissingComponent(component) => {
+                write!(f, "manifest has no {} component", component)
+            }
+            Self::ComponentVersion {
+                component,
+                found,
+                supported,
+            } => write!(
+                f,
+                "unsupported {} version {} (supported version is {})",
+                component, found, supported
+            ),
+            Self::Invalid(reason) => write!(f, "invalid saved state: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
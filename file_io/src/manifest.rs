@@ -0,0 +1,76 @@
+use crate::{create_file_buf_write, open_file_buf_read, LoadError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+
+// The version of the manifest format itself. The formats of the components
+// stored next to a manifest are versioned separately, by the components
+pub const FORMAT_VERSION: u32 = 1;
+const FILE_NAME: &str = "manifest.json";
+
+// Describes the contents of a directory of saved state. The manifest records
+// which components were saved into the directory along with the version of
+// each component's format, so that loaders can reject state written in a
+// format they don't understand instead of misinterpreting it. It also records
+// the configuration that was in use while saving, for reference
+#[derive(Serialize, Deserialize)]
+pub struct Manifest {
+    format_version: u32,
+    components: BTreeMap<String, u32>,
+    config: BTreeMap<String, String>,
+}
+
+impl Manifest {
+    pub fn new() -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            components: BTreeMap::new(),
+            config: BTreeMap::new(),
+        }
+    }
+    pub fn add_component(&mut self, component: &str, version: u32) {
+        self.components.insert(component.to_string(), version);
+    }
+    pub fn add_config<V: ToString>(&mut self, key: &str, value: V) {
+        self.config.insert(key.to_string(), value.to_string());
+    }
+    pub fn config(&self, key: &str) -> Option<&str> {
+        self.config.get(key).map(String::as_str)
+    }
+    pub fn check_component(&self, component: &str, supported: u32) -> Result<(), LoadError> {
+        match self.components.get(component) {
+            Some(&found) if found == supported => Ok(()),
+            Some(&found) => Err(LoadError::ComponentVersion {
+                component: component.to_string(),
+                found,
+                supported,
+            }),
+            None => Err(LoadError::MissingComponent(component.to_string())),
+        }
+    }
+    pub fn save<P: AsRef<Path>>(&self, dir: P) -> io::Result<()> {
+        let file = create_file_buf_write(dir.as_ref().join(FILE_NAME))?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+    pub fn load<P: AsRef<Path>>(dir: P) -> Result<Self, LoadError> {
+        let path = dir.as_ref().join(FILE_NAME);
+        let file = open_file_buf_read(&path).map_err(|e| LoadError::Io(path.clone(), e))?;
+        let manifest: Self =
+            serde_json::from_reader(file).map_err(|e| LoadError::JsonDecode(path, e))?;
+        if manifest.format_version != FORMAT_VERSION {
+            return Err(LoadError::FormatVersion {
+                found: manifest.format_version,
+                supported: FORMAT_VERSION,
+            });
+        }
+        Ok(manifest)
+    }
+}
+
+impl Default for Manifest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
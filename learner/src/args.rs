@@ -7,4 +7,20 @@ pub struct Args {
     /// Path from which the model definition should be loaded.
     #[arg(short, long, default_value = "model")]
     pub model_def_path: PathBuf,
+    /// Path of a saved replay memory to train on offline, without actors or a
+    /// replay server.
+    ///
+    /// If unset, connects to the coordinator and trains on the replay server
+    #[arg(long, required = false)]
+    pub offline_replay_path: Option<PathBuf>,
+    /// The number of training steps to run in offline mode.
+    #[arg(long, default_value_t = 1_000_000)]
+    pub offline_steps: u32,
+    /// Path to which checkpoints and plots are written in offline mode.
+    #[arg(long, default_value = "offline")]
+    pub offline_output_path: PathBuf,
+    /// The number of training steps between consecutive checkpoints in offline
+    /// mode.
+    #[arg(long, default_value_t = 100_000)]
+    pub offline_checkpoint_interval_steps: u32,
 }
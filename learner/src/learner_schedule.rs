@@ -1,4 +1,4 @@
-use file_io::{create_file_buf_write, has_data_left, open_file_buf_read};
+use file_io::{create_file_buf_write, load_bincode, LoadError, Manifest};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 
@@ -10,6 +10,9 @@ pub struct LearnerSchedule {
 }
 
 impl LearnerSchedule {
+    const COMPONENT: &'static str = "schedule";
+    const FORMAT_VERSION: u32 = 1;
+
     pub fn new(target_update_interval_steps: u32, truncate_memory_interval_steps: u32) -> Self {
         Self {
             n_step: 0,
@@ -29,16 +32,14 @@ impl LearnerSchedule {
     pub fn is_time_to_truncate_memory(&self) -> bool {
         self.n_step % self.truncate_memory_interval_steps == 0
     }
-    pub fn save<P: AsRef<Path>>(&self, path: P) {
+    pub fn save<P: AsRef<Path>>(&self, path: P, manifest: &mut Manifest) {
         let file = create_file_buf_write(path.as_ref().join("schedule")).unwrap();
         bincode::serialize_into(file, self).unwrap();
+        manifest.add_component(Self::COMPONENT, Self::FORMAT_VERSION);
     }
-    pub fn load<P: AsRef<Path>>(&mut self, path: P) {
-        let mut file = open_file_buf_read(path.as_ref().join("schedule")).unwrap();
-        *self = bincode::deserialize_from(&mut file).unwrap();
-        assert!(
-            !has_data_left(file).unwrap(),
-            "deserialization of file didn't reach EOF"
-        );
+    pub fn load<P: AsRef<Path>>(&mut self, path: P, manifest: &Manifest) -> Result<(), LoadError> {
+        manifest.check_component(Self::COMPONENT, Self::FORMAT_VERSION)?;
+        *self = load_bincode(path.as_ref().join("schedule"))?;
+        Ok(())
     }
 }
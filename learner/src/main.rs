@@ -1,6 +1,7 @@
 mod args;
 mod learner_plot_remote;
 mod learner_schedule;
+mod offline;
 
 use args::Args;
 use clap::Parser;
@@ -9,7 +10,6 @@ use learner_plot_remote::LearnerPlotRemote;
 use learner_schedule::LearnerSchedule;
 use local_ip_address::local_ip;
 use model::traits::{ParamFetcher, TargetNet};
-use model::BasicModel;
 use packets::{GetParamsReply, LearnerRequest, LearnerSettings};
 use prompt::prompt_user_for_service_ip_addr;
 use replay_wrappers::RemoteReplayWrapper;
@@ -17,20 +17,52 @@ use std::net::{Ipv4Addr, SocketAddr, TcpListener};
 use std::sync::{Arc, RwLock};
 use std::thread::JoinHandle;
 
+// the model backend, chosen by the crate's features
+#[cfg(feature = "tensorflow")]
+type Model = model::BasicModel;
+#[cfg(all(feature = "reference", not(feature = "tensorflow")))]
+type Model = model::ReferenceModel;
+#[cfg(not(any(feature = "tensorflow", feature = "reference")))]
+compile_error!("a model backend must be enabled with the tensorflow or reference feature");
+
+fn alpha(n_step: u32) -> f64 {
+    const ALPHA_START: f64 = 0.6;
+    const ALPHA_END: f64 = 0.4;
+    const N_ALPHA_ANNEALING_STEPS: u32 = 200_000;
+    let alpha = ALPHA_START
+        + (ALPHA_END - ALPHA_START) * f64::from(n_step) / f64::from(N_ALPHA_ANNEALING_STEPS);
+    alpha.max(ALPHA_END)
+}
+
 fn spawn_batch_learner_thread(
-    agent: Arc<RwLock<RemoteReplayWrapper<BasicModel>>>,
+    agent: Arc<RwLock<RemoteReplayWrapper<Model>>>,
     plot_server_addr: Option<SocketAddr>,
 ) -> JoinHandle<()> {
     std::thread::spawn(move || {
         const TARGET_UPDATE_INTERVAL_STEPS: u32 = 2_500;
         const TRUNCATE_MEMORY_INTERVAL_STEPS: u32 = 100;
         const BETA: f64 = 0.4;
+        // every alpha change makes the replay server rebuild its whole priority
+        // tree before serving anything else, so alpha is only changed every
+        // few thousand steps, and not at all once the annealing is over
+        const ALPHA_UPDATE_INTERVAL_STEPS: u32 = 5_000;
+        let mut last_alpha = None;
         let mut schedule =
             LearnerSchedule::new(TARGET_UPDATE_INTERVAL_STEPS, TRUNCATE_MEMORY_INTERVAL_STEPS);
         let mut plot_remote = plot_server_addr.map(|addr| LearnerPlotRemote::new(addr, 100));
         loop {
             {
                 let mut agent = agent.write().unwrap();
+                if schedule
+                    .n_step()
+                    .is_multiple_of(ALPHA_UPDATE_INTERVAL_STEPS)
+                {
+                    let alpha = alpha(schedule.n_step());
+                    if last_alpha != Some(alpha) {
+                        agent.set_alpha(alpha);
+                        last_alpha = Some(alpha);
+                    }
+                }
                 if let Some(step_info) = agent.train_step(BETA) {
                     if let Some(ref mut plot_remote) = plot_remote {
                         plot_remote.send(step_info);
@@ -50,7 +82,7 @@ fn spawn_batch_learner_thread(
 
 fn spawn_param_server_thread(
     socket: TcpListener,
-    agent: Arc<RwLock<RemoteReplayWrapper<BasicModel>>>,
+    agent: Arc<RwLock<RemoteReplayWrapper<Model>>>,
 ) -> JoinHandle<()> {
     std::thread::spawn(move || loop {
         let (stream, _source_addr) = socket.accept().unwrap();
@@ -114,6 +146,11 @@ fn main() {
     // to circumvent the memory issue
     enable_tf_memory_growth();
 
+    if let Some(ref replay_path) = args.offline_replay_path {
+        offline::run(replay_path, &args);
+        return;
+    }
+
     let coordinator_ip_addr = prompt_user_for_service_ip_addr("coordinator");
     println!("coordinator ip addr set to {}...", coordinator_ip_addr);
     let coordinator_addr = (coordinator_ip_addr, ports::COORDINATOR).into();
@@ -127,11 +164,20 @@ fn main() {
 }
 
 fn run(socket: TcpListener, args: Args, settings: LearnerSettings) {
-    const ALPHA: f64 = 0.6;
+    let model = match Model::new(&args.model_def_path) {
+        Ok(model) => model,
+        Err(e) => {
+            eprintln!(
+                "could not load model {}: {}",
+                args.model_def_path.display(),
+                e
+            );
+            return;
+        }
+    };
     let agent = Arc::new(RwLock::new(RemoteReplayWrapper::wrap(
-        BasicModel::new(args.model_def_path),
+        model,
         settings.replay_server_addr,
-        ALPHA,
     )));
     let batch_learner_thread =
         spawn_batch_learner_thread(Arc::clone(&agent), settings.plot_server_addr);
@@ -0,0 +1,84 @@
+use crate::args::Args;
+use crate::Model;
+use file_io::checkpoint;
+use model::traits::{Persistable, TargetNet};
+use plot::Plot;
+use replay_wrappers::PrioritizedReplayWrapper;
+use std::fs;
+use std::path::Path;
+
+fn save_checkpoint(agent: &PrioritizedReplayWrapper<Model>, output_path: &Path, n_step: u32) {
+    let checkpoints_path = output_path.join("checkpoints");
+    fs::create_dir_all(&checkpoints_path).unwrap();
+    let checkpoint_path = checkpoints_path.join(n_step.to_string());
+    let staging_path = checkpoint::begin(&checkpoint_path).unwrap();
+    agent.model().save(staging_path.join("model_vars"));
+    checkpoint::commit(&checkpoint_path).unwrap();
+    println!("saved checkpoint to {}", checkpoint_path.display());
+}
+
+fn alpha(n_step: u32, n_steps: u32) -> f64 {
+    const ALPHA_START: f64 = 0.6;
+    const ALPHA_END: f64 = 0.4;
+    let alpha = ALPHA_START + (ALPHA_END - ALPHA_START) * f64::from(n_step) / f64::from(n_steps);
+    alpha.max(ALPHA_END)
+}
+
+fn beta(n_step: u32, n_steps: u32) -> f64 {
+    const BETA_START: f64 = 0.4;
+    const BETA_END: f64 = 1.0;
+    let beta = BETA_START + (BETA_END - BETA_START) * f64::from(n_step) / f64::from(n_steps);
+    beta.min(BETA_END)
+}
+
+// Trains on a frozen replay snapshot instead of a live replay server. The
+// snapshot's priorities are still updated during training, as they would be
+// online, but no new transitions are ever added
+pub fn run(replay_path: &Path, args: &Args) {
+    const TARGET_UPDATE_INTERVAL_STEPS: u32 = 2_500;
+    // changing alpha rebuilds the snapshot's priorities, so it is only
+    // changed every few steps
+    const ALPHA_UPDATE_INTERVAL_STEPS: u32 = 1_000;
+    const PLOT_DATA_PER_POINT: usize = 100;
+    let model = match Model::new(&args.model_def_path) {
+        Ok(model) => model,
+        Err(e) => {
+            eprintln!(
+                "could not load model {}: {}",
+                args.model_def_path.display(),
+                e
+            );
+            return;
+        }
+    };
+    println!("loading replay from {}...", replay_path.display());
+    let agent = PrioritizedReplayWrapper::wrap_saved_memory(model, replay_path);
+    let mut agent = match agent {
+        Ok(agent) => agent,
+        Err(e) => {
+            eprintln!("could not load {}: {}", replay_path.display(), e);
+            return;
+        }
+    };
+    println!("loaded {} transitions", agent.memory_len());
+    let output_path = &args.offline_output_path;
+    let plots_path = output_path.join("plots");
+    let mut loss_plot = Plot::new(plots_path.clone(), "loss".into(), PLOT_DATA_PER_POINT);
+    let mut q_val_plot = Plot::new(plots_path, "q_val".into(), PLOT_DATA_PER_POINT);
+    for n_step in 0..args.offline_steps {
+        if n_step % ALPHA_UPDATE_INTERVAL_STEPS == 0 {
+            agent.set_alpha(alpha(n_step, args.offline_steps));
+        }
+        if let Some(step_info) = agent.train_step(beta(n_step, args.offline_steps)) {
+            loss_plot.add_datum((n_step.into(), step_info.loss.into()));
+            q_val_plot.add_datum((n_step.into(), step_info.average_q_val.into()));
+        }
+        if n_step % TARGET_UPDATE_INTERVAL_STEPS == 0 {
+            agent.copy_control_to_target();
+        }
+        if n_step % args.offline_checkpoint_interval_steps == 0 {
+            save_checkpoint(&agent, output_path, n_step);
+        }
+    }
+    save_checkpoint(&agent, output_path, args.offline_steps);
+}
@@ -1,39 +1,124 @@
 mod model_fns;
-mod to_pixels;
+mod model_load_error;
+mod state_shape;
 
 use super::traits::{
-    Actor, BasicLearner, ParamFetcher, Persistable, PrioritizedLearner, TargetNet,
+    Actor, BasicLearner, ParamFetcher, Persistable, PrioritizedLearner, TargetNet, TdErrorEstimator,
 };
 use super::LearningStepInfo;
+use crate::model_metadata::ModelMetadata;
+use crate::state_shape::StateShape;
+use crate::to_pixels::ToPixels;
 use crate::Params;
 use model_fns::ModelFns;
+pub use model_load_error::{ModelLoadError, SignatureProblem};
 use replay_data::GenericTransition;
 use std::path::Path;
 use tensorflow::{Graph, SavedModelBundle, SessionOptions, Tensor};
-use to_pixels::ToPixels;
 
 pub struct BasicModel {
     model_bundle: SavedModelBundle,
     fns: ModelFns,
+    metadata: ModelMetadata,
+    state_shape: StateShape,
 }
 
 impl BasicModel {
-    pub fn new<P: AsRef<Path>>(def_path: P) -> Self {
+    pub fn new<P: AsRef<Path>>(def_path: P) -> Result<Self, ModelLoadError> {
+        Self::load_model(def_path.as_ref(), false)
+    }
+
+    // for models that are only used to act, which may leave out the functions
+    // that only training needs. Calling one of those panics
+    pub fn for_inference<P: AsRef<Path>>(def_path: P) -> Result<Self, ModelLoadError> {
+        Self::load_model(def_path.as_ref(), true)
+    }
+
+    fn load_model(def_path: &Path, inference_only: bool) -> Result<Self, ModelLoadError> {
         let mut graph = Graph::new();
         let model_bundle =
             SavedModelBundle::load(&SessionOptions::new(), ["serve"], &mut graph, def_path)
-                .expect("Couldn't load model");
-        let fns = ModelFns::new(&model_bundle, &graph);
-        Self { model_bundle, fns }
+                .map_err(|status| ModelLoadError::Bundle(def_path.to_path_buf(), status))?;
+        let fns = ModelFns::new(&model_bundle, &graph, inference_only)
+            .map_err(ModelLoadError::Signatures)?;
+        let state_shape = StateShape::from_model(&model_bundle)?;
+        let metadata = match ModelMetadata::load(def_path).map_err(ModelLoadError::MetadataLoad)? {
+            Some(metadata) if metadata.state_shape != state_shape.dims() => {
+                return Err(ModelLoadError::Metadata(format!(
+                    "state shape {:?} doesn't match the model's {:?}",
+                    metadata.state_shape,
+                    state_shape.dims()
+                )));
+            }
+            Some(metadata) => metadata,
+            None => {
+                eprintln!(
+                    "{} has no metadata, so it is assumed to have been created with the \
+                     settings that create_model.py used before it wrote any. Re-exporting \
+                     the model with create_model.py adds the metadata",
+                    def_path.display()
+                );
+                ModelMetadata::legacy(state_shape.dims())
+            }
+        };
+        Ok(Self {
+            model_bundle,
+            fns,
+            metadata,
+            state_shape,
+        })
+    }
+
+    // false for inference-only models without the function
+    pub fn can_compute_abs_td_errors(&self) -> bool {
+        self.fns.compute_abs_td_errors.is_loaded()
+    }
+
+    // false for models created before create_model.py exported q_vals_batch,
+    // which can still act on one state at a time through best_action
+    pub fn can_compute_q_vals_batch(&self) -> bool {
+        self.fns.q_vals_batch.is_loaded()
     }
 
-    pub fn compute_abs_td_errors<State>(&self, batch: &[&GenericTransition<State>]) -> Vec<f32>
-    where
-        State: ToPixels,
-    {
+    pub fn metadata(&self) -> &ModelMetadata {
+        &self.metadata
+    }
+
+    pub fn state_shape(&self) -> StateShape {
+        self.state_shape
+    }
+
+    fn state_tensor(&self, state: &[u8]) -> Tensor<u8> {
+        Tensor::new(&self.state_shape.dims())
+            .with_values(state)
+            .unwrap_or_else(|_| {
+                panic!(
+                    "state doesn't match the state shape of the model, {:?}",
+                    self.state_shape
+                )
+            })
+    }
+
+    fn states_tensor(&self, batch_len: u64, states: &[u8]) -> Tensor<u8> {
+        Tensor::new(&self.state_shape.batch_dims(batch_len))
+            .with_values(states)
+            .unwrap_or_else(|_| {
+                panic!(
+                    "states don't match the state shape of the model, {:?}",
+                    self.state_shape
+                )
+            })
+    }
+}
+
+impl<State> TdErrorEstimator<GenericTransition<State>> for BasicModel
+where
+    State: ToPixels,
+{
+    fn compute_abs_td_errors(&self, batch: &[&GenericTransition<State>]) -> Vec<f64> {
         let batch_len = batch.len();
-        let mut states = Vec::with_capacity(batch_len * 8 * 72 * 128);
-        let mut next_states = Vec::with_capacity(batch_len * 8 * 72 * 128);
+        let mut states = Vec::with_capacity(batch_len * self.state_shape.pixel_count());
+        let mut next_states = Vec::with_capacity(batch_len * self.state_shape.pixel_count());
         let mut actions = Vec::with_capacity(batch_len);
         let mut rewards = Vec::with_capacity(batch_len);
         let mut dones = Vec::with_capacity(batch_len);
@@ -46,17 +131,13 @@ impl BasicModel {
         }
 
         let batch_len = batch_len.try_into().unwrap();
-        let states_arg = Tensor::new(&[batch_len, 8, 72, 128])
-            .with_values(&states)
-            .unwrap();
-        let next_states_arg = Tensor::new(&[batch_len, 8, 72, 128])
-            .with_values(&next_states)
-            .unwrap();
+        let states_arg = self.states_tensor(batch_len, &states);
+        let next_states_arg = self.states_tensor(batch_len, &next_states);
         let actions_arg = Tensor::new(&[batch_len]).with_values(&actions).unwrap();
         let rewards_arg = Tensor::new(&[batch_len]).with_values(&rewards).unwrap();
         let dones_arg = Tensor::new(&[batch_len]).with_values(&dones).unwrap();
 
-        let (abs_td_errors,): (Tensor<f32>,) = self.fns.compute_abs_td_errors.call(
+        let (abs_td_errors,): (Tensor<f32>,) = self.fns.compute_abs_td_errors.get().call(
             &self.model_bundle.session,
             (
                 states_arg,
@@ -66,7 +147,7 @@ impl BasicModel {
                 dones_arg,
             ),
         );
-        abs_td_errors.to_vec()
+        abs_td_errors.iter().map(|x| *x as f64).collect::<Vec<_>>()
     }
 }
 
@@ -75,17 +156,42 @@ where
     State: ToPixels,
 {
     fn best_action(&self, state: &State) -> u8 {
-        let state_values = state.to_pixels();
-        let state_arg = Tensor::new(&[8, 72, 128])
-            .with_values(&state_values)
-            .unwrap();
-
+        let state_arg = self.state_tensor(&state.to_pixels());
         let (action,): (Tensor<i64>,) = self
             .fns
             .best_action
             .call(&self.model_bundle.session, (state_arg,));
         action.get(&[]) as u8
     }
+    fn q_vals(&self, state: &State) -> Vec<f32> {
+        let state_arg = self.state_tensor(&state.to_pixels());
+        let (q_vals,): (Tensor<f32>,) = self
+            .fns
+            .q_vals
+            .get()
+            .call(&self.model_bundle.session, (state_arg,));
+        q_vals.to_vec()
+    }
+    fn q_vals_batch(&self, states: &[&State]) -> Vec<Vec<f32>> {
+        if states.is_empty() {
+            return vec![];
+        }
+        let mut states_values = Vec::with_capacity(states.len() * self.state_shape.pixel_count());
+        for state in states {
+            states_values.extend(state.to_pixels());
+        }
+        let states_arg = self.states_tensor(states.len().try_into().unwrap(), &states_values);
+        let (q_vals,): (Tensor<f32>,) = self
+            .fns
+            .q_vals_batch
+            .get()
+            .call(&self.model_bundle.session, (states_arg,));
+        let n_actions = q_vals.dims()[1].try_into().unwrap();
+        q_vals
+            .chunks(n_actions)
+            .map(|state_q_vals| state_q_vals.to_vec())
+            .collect()
+    }
 }
 
 impl<State> BasicLearner<GenericTransition<State>> for BasicModel
@@ -94,8 +200,8 @@ where
 {
     fn train_batch(&mut self, batch: &[&GenericTransition<State>]) -> LearningStepInfo {
         let batch_len = batch.len();
-        let mut states = Vec::with_capacity(batch_len * 8 * 72 * 128);
-        let mut next_states = Vec::with_capacity(batch_len * 8 * 72 * 128);
+        let mut states = Vec::with_capacity(batch_len * self.state_shape.pixel_count());
+        let mut next_states = Vec::with_capacity(batch_len * self.state_shape.pixel_count());
         let mut actions = Vec::with_capacity(batch_len);
         let mut rewards = Vec::with_capacity(batch_len);
         let mut dones = Vec::with_capacity(batch_len);
@@ -108,17 +214,13 @@ where
         }
 
         let batch_len = batch_len.try_into().unwrap();
-        let states_arg = Tensor::new(&[batch_len, 8, 72, 128])
-            .with_values(&states)
-            .unwrap();
-        let next_states_arg = Tensor::new(&[batch_len, 8, 72, 128])
-            .with_values(&next_states)
-            .unwrap();
+        let states_arg = self.states_tensor(batch_len, &states);
+        let next_states_arg = self.states_tensor(batch_len, &next_states);
         let actions_arg = Tensor::new(&[batch_len]).with_values(&actions).unwrap();
         let rewards_arg = Tensor::new(&[batch_len]).with_values(&rewards).unwrap();
         let dones_arg = Tensor::new(&[batch_len]).with_values(&dones).unwrap();
 
-        let (loss, average_q_val): (Tensor<f32>, Tensor<f32>) = self.fns.train_batch.call(
+        let (loss, average_q_val): (Tensor<f32>, Tensor<f32>) = self.fns.train_batch.get().call(
             &self.model_bundle.session,
             (
                 states_arg,
@@ -148,8 +250,8 @@ where
         beta: f64,
     ) -> (LearningStepInfo, Vec<f64>) {
         let batch_len = batch_transitions.len();
-        let mut states = Vec::with_capacity(batch_len * 8 * 72 * 128);
-        let mut next_states = Vec::with_capacity(batch_len * 8 * 72 * 128);
+        let mut states = Vec::with_capacity(batch_len * self.state_shape.pixel_count());
+        let mut next_states = Vec::with_capacity(batch_len * self.state_shape.pixel_count());
         let mut actions = Vec::with_capacity(batch_len);
         let mut rewards = Vec::with_capacity(batch_len);
         let mut dones = Vec::with_capacity(batch_len);
@@ -162,12 +264,8 @@ where
         }
 
         let batch_len = batch_len.try_into().unwrap();
-        let states_arg = Tensor::new(&[batch_len, 8, 72, 128])
-            .with_values(&states)
-            .unwrap();
-        let next_states_arg = Tensor::new(&[batch_len, 8, 72, 128])
-            .with_values(&next_states)
-            .unwrap();
+        let states_arg = self.states_tensor(batch_len, &states);
+        let next_states_arg = self.states_tensor(batch_len, &next_states);
         let actions_arg = Tensor::new(&[batch_len]).with_values(&actions).unwrap();
         let rewards_arg = Tensor::new(&[batch_len]).with_values(&rewards).unwrap();
         let dones_arg = Tensor::new(&[batch_len]).with_values(&dones).unwrap();
@@ -202,6 +300,7 @@ where
         let (loss, average_q_val, abs_td_errors): (Tensor<f32>, Tensor<f32>, Tensor<f32>) = self
             .fns
             .train_batch_prioritized
+            .get()
             .call(&self.model_bundle.session, args);
         let learning_step_info = LearningStepInfo {
             loss: loss.get(&[]),
@@ -219,6 +318,7 @@ impl TargetNet for BasicModel {
         let (_,): (Tensor<i32>,) = self
             .fns
             .copy_control_to_target
+            .get()
             .call(&self.model_bundle.session, ());
     }
 }
@@ -245,7 +345,8 @@ impl Persistable for BasicModel {
 
 impl ParamFetcher for BasicModel {
     fn params(&self) -> Params {
-        let (params,): (Tensor<String>,) = self.fns.params.call(&self.model_bundle.session, ());
+        let (params,): (Tensor<String>,) =
+            self.fns.params.get().call(&self.model_bundle.session, ());
         Params(params.to_vec())
     }
     fn set_params(&mut self, params: Params) {
@@ -1,78 +1,225 @@
 mod tensorflow_fn;
 
-use tensorflow::{Graph, SavedModelBundle};
-use tensorflow_fn::TensorflowFn;
+use super::model_load_error::SignatureProblem;
+use tensorflow::{DataType, Graph, SavedModelBundle};
+use tensorflow_fn::{TensorSpec, TensorflowFn};
+
+// A function that the model may leave out, like the functions that only
+// training needs in inference-only models, or the q value functions that older
+// models lack
+pub struct OptionalFn<const N: usize, const M: usize> {
+    name: &'static str,
+    function: Option<TensorflowFn<N, M>>,
+}
+
+impl<const N: usize, const M: usize> OptionalFn<N, M> {
+    pub fn is_loaded(&self) -> bool {
+        self.function.is_some()
+    }
+    pub fn get(&self) -> &TensorflowFn<N, M> {
+        self.function
+            .as_ref()
+            .unwrap_or_else(|| panic!("the model was loaded without its {} function", self.name))
+    }
+}
 
 pub struct ModelFns {
     pub best_action: TensorflowFn<1, 1>,
-    pub train_batch: TensorflowFn<5, 2>,
-    pub train_batch_prioritized: TensorflowFn<9, 3>,
-    pub compute_abs_td_errors: TensorflowFn<5, 1>,
-    pub copy_control_to_target: TensorflowFn<0, 1>,
+    pub q_vals: OptionalFn<1, 1>,
+    pub q_vals_batch: OptionalFn<1, 1>,
+    pub train_batch: OptionalFn<5, 2>,
+    pub train_batch_prioritized: OptionalFn<9, 3>,
+    pub compute_abs_td_errors: OptionalFn<5, 1>,
+    pub copy_control_to_target: OptionalFn<0, 1>,
     pub save: TensorflowFn<1, 1>,
     pub load: TensorflowFn<1, 1>,
-    pub params: TensorflowFn<0, 1>,
+    pub params: OptionalFn<0, 1>,
     pub set_params: TensorflowFn<1, 1>,
 }
 
+fn states(name: &'static str) -> TensorSpec {
+    TensorSpec::new(name, DataType::UInt8, 4)
+}
+
+fn batch(name: &'static str, dtype: DataType) -> TensorSpec {
+    TensorSpec::new(name, dtype, 1)
+}
+
+fn scalar(name: &'static str, dtype: DataType) -> TensorSpec {
+    TensorSpec::new(name, dtype, 0)
+}
+
+// the transition batches that the training functions take
+fn transition_specs() -> [TensorSpec; 5] {
+    [
+        states("states"),
+        states("new_states"),
+        batch("actions", DataType::UInt8),
+        batch("rewards", DataType::Float),
+        batch("dones", DataType::Float),
+    ]
+}
+
+// the output of functions that only return a dummy value
+fn done_output() -> [(TensorSpec, i32); 1] {
+    [(scalar("output_0", DataType::Int32), 0)]
+}
+
+struct FnLoader<'a> {
+    model_bundle: &'a SavedModelBundle,
+    graph: &'a Graph,
+    inference_only: bool,
+    problems: Vec<SignatureProblem>,
+}
+
+impl FnLoader<'_> {
+    fn required<const N: usize, const M: usize>(
+        &mut self,
+        name: &'static str,
+        arg_specs: [TensorSpec; N],
+        output_specs: [(TensorSpec, i32); M],
+    ) -> Option<TensorflowFn<N, M>> {
+        match TensorflowFn::new(self.model_bundle, self.graph, name, arg_specs, output_specs) {
+            Ok(function) => Some(function),
+            Err(problems) => {
+                self.problems.extend(problems);
+                None
+            }
+        }
+    }
+    // functions of inference-only models are only checked if they exist
+    fn training<const N: usize, const M: usize>(
+        &mut self,
+        name: &'static str,
+        arg_specs: [TensorSpec; N],
+        output_specs: [(TensorSpec, i32); M],
+    ) -> OptionalFn<N, M> {
+        let may_be_missing = self.inference_only;
+        self.optional_if(may_be_missing, name, arg_specs, output_specs)
+    }
+    // functions that older models lack are only checked if they exist
+    fn optional<const N: usize, const M: usize>(
+        &mut self,
+        name: &'static str,
+        arg_specs: [TensorSpec; N],
+        output_specs: [(TensorSpec, i32); M],
+    ) -> OptionalFn<N, M> {
+        self.optional_if(true, name, arg_specs, output_specs)
+    }
+    fn optional_if<const N: usize, const M: usize>(
+        &mut self,
+        may_be_missing: bool,
+        name: &'static str,
+        arg_specs: [TensorSpec; N],
+        output_specs: [(TensorSpec, i32); M],
+    ) -> OptionalFn<N, M> {
+        let function =
+            match TensorflowFn::new(self.model_bundle, self.graph, name, arg_specs, output_specs) {
+                Ok(function) => Some(function),
+                Err(problems)
+                    if may_be_missing
+                        && matches!(problems[..], [SignatureProblem::MissingSignature(_)]) =>
+                {
+                    None
+                }
+                Err(problems) => {
+                    self.problems.extend(problems);
+                    None
+                }
+            };
+        OptionalFn { name, function }
+    }
+}
+
 impl ModelFns {
-    pub fn new(model_bundle: &SavedModelBundle, graph: &Graph) -> Self {
-        Self {
-            best_action: TensorflowFn::new(
-                model_bundle,
-                graph,
-                "best_action",
-                ["state"],
-                [("output_0", 0)],
-            ),
-            train_batch: TensorflowFn::new(
-                model_bundle,
-                graph,
-                "train_pred_step",
-                ["states", "new_states", "actions", "rewards", "dones"],
-                [("output_0", 0), ("output_1", 1)],
-            ),
-            train_batch_prioritized: TensorflowFn::new(
-                model_bundle,
-                graph,
-                "train_pred_step_prioritized",
-                [
-                    "states",
-                    "new_states",
-                    "actions",
-                    "rewards",
-                    "dones",
-                    "probabilities",
-                    "min_probability",
-                    "replay_memory_len",
-                    "beta",
-                ],
-                [("output_0", 0), ("output_1", 1), ("output_2", 2)],
-            ),
-            compute_abs_td_errors: TensorflowFn::new(
-                model_bundle,
-                graph,
-                "compute_abs_td_errors",
-                ["states", "new_states", "actions", "rewards", "dones"],
-                [("output_0", 0)],
-            ),
-            copy_control_to_target: TensorflowFn::new(
-                model_bundle,
-                graph,
-                "copy_control_to_target",
-                [],
-                [("output_0", 0)],
-            ),
-            save: TensorflowFn::new(model_bundle, graph, "save", ["path"], [("output_0", 0)]),
-            load: TensorflowFn::new(model_bundle, graph, "load", ["path"], [("output_0", 0)]),
-            params: TensorflowFn::new(model_bundle, graph, "get_params", [], [("output_0", 0)]),
-            set_params: TensorflowFn::new(
-                model_bundle,
-                graph,
-                "set_params",
-                ["params"],
-                [("output_0", 0)],
-            ),
+    pub fn new(
+        model_bundle: &SavedModelBundle,
+        graph: &Graph,
+        inference_only: bool,
+    ) -> Result<Self, Vec<SignatureProblem>> {
+        let mut loader = FnLoader {
+            model_bundle,
+            graph,
+            inference_only,
+            problems: vec![],
+        };
+        let best_action = loader.required(
+            "best_action",
+            [TensorSpec::new("state", DataType::UInt8, 3)],
+            [(scalar("output_0", DataType::Int64), 0)],
+        );
+        // older models only have best_action to act with
+        let q_vals = loader.optional(
+            "q_vals",
+            [TensorSpec::new("state", DataType::UInt8, 3)],
+            [(batch("output_0", DataType::Float), 0)],
+        );
+        let q_vals_batch = loader.optional(
+            "q_vals_batch",
+            [states("states")],
+            [(TensorSpec::new("output_0", DataType::Float, 2), 0)],
+        );
+        let train_batch = loader.training(
+            "train_pred_step",
+            transition_specs(),
+            [
+                (scalar("output_0", DataType::Float), 0),
+                (scalar("output_1", DataType::Float), 1),
+            ],
+        );
+        let [states, new_states, actions, rewards, dones] = transition_specs();
+        let train_batch_prioritized = loader.training(
+            "train_pred_step_prioritized",
+            [
+                states,
+                new_states,
+                actions,
+                rewards,
+                dones,
+                batch("probabilities", DataType::Float),
+                scalar("min_probability", DataType::Float),
+                scalar("replay_memory_len", DataType::Float),
+                scalar("beta", DataType::Float),
+            ],
+            [
+                (scalar("output_0", DataType::Float), 0),
+                (scalar("output_1", DataType::Float), 1),
+                (batch("output_2", DataType::Float), 2),
+            ],
+        );
+        let compute_abs_td_errors = loader.training(
+            "compute_abs_td_errors",
+            transition_specs(),
+            [(batch("output_0", DataType::Float), 0)],
+        );
+        let copy_control_to_target = loader.training("copy_control_to_target", [], done_output());
+        let save = loader.required("save", [scalar("path", DataType::String)], done_output());
+        let load = loader.required("load", [scalar("path", DataType::String)], done_output());
+        let params = loader.training("get_params", [], [(batch("output_0", DataType::String), 0)]);
+        let set_params = loader.required(
+            "set_params",
+            [batch("params", DataType::String)],
+            done_output(),
+        );
+        match (best_action, save, load, set_params) {
+            (Some(best_action), Some(save), Some(load), Some(set_params))
+                if loader.problems.is_empty() =>
+            {
+                Ok(Self {
+                    best_action,
+                    q_vals,
+                    q_vals_batch,
+                    train_batch,
+                    train_batch_prioritized,
+                    compute_abs_td_errors,
+                    copy_control_to_target,
+                    save,
+                    load,
+                    params,
+                    set_params,
+                })
+            }
+            _ => Err(loader.problems),
         }
     }
 }
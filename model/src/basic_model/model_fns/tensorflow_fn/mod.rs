@@ -2,12 +2,17 @@ mod arg;
 mod arg_location;
 mod output;
 mod output_location;
+mod tensor_spec;
 
+use crate::basic_model::model_load_error::SignatureProblem;
 use arg::ArgTuple;
 use arg_location::ArgLocation;
 use output::OutputTuple;
 use output_location::OutputLocation;
-use tensorflow::{Graph, SavedModelBundle, Session, SessionRunArgs};
+pub use tensor_spec::TensorSpec;
+use tensorflow::{
+    Graph, Operation, SavedModelBundle, Session, SessionRunArgs, SignatureDef, TensorInfo,
+};
 
 pub struct TensorflowFn<const N: usize, const M: usize> {
     pub arg_locations: [ArgLocation; N],
@@ -15,29 +20,99 @@ pub struct TensorflowFn<const N: usize, const M: usize> {
 }
 
 impl<const N: usize, const M: usize> TensorflowFn<N, M> {
+    // fails with every way in which the function doesn't match the specs
     pub fn new(
         model_bundle: &SavedModelBundle,
         graph: &Graph,
         name: &str,
-        arg_names: [&str; N],
-        output_names: [(&str, i32); M],
-    ) -> Self {
-        let signature = model_bundle.meta_graph_def().get_signature(name).unwrap();
-        let arg_infos = arg_names.map(|arg_name| signature.get_input(arg_name).unwrap());
-        let arg_ops = arg_infos.map(|arg_info| {
-            graph
-                .operation_by_name_required(&arg_info.name().name)
-                .unwrap()
+        arg_specs: [TensorSpec; N],
+        output_specs: [(TensorSpec, i32); M],
+    ) -> Result<Self, Vec<SignatureProblem>> {
+        let signature = model_bundle
+            .meta_graph_def()
+            .get_signature(name)
+            .map_err(|_| vec![SignatureProblem::MissingSignature(name.to_string())])?;
+        let mut problems = vec![];
+        let arg_ops = arg_specs.map(|spec| {
+            let Ok(info) = signature.get_input(spec.name) else {
+                problems.push(SignatureProblem::MissingInput {
+                    signature: name.to_string(),
+                    input: spec.name.to_string(),
+                });
+                return None;
+            };
+            Self::check_tensor(graph, name, info, &spec, &mut problems)
         });
-        let arg_locations = arg_ops.map(ArgLocation::new);
-        let output_locations = output_names.map(|(name, index)| {
-            let info = signature.get_output(name).unwrap();
-            let op = graph.operation_by_name_required(&info.name().name).unwrap();
+        let output_ops = output_specs.map(|(spec, index)| {
+            let op = Self::output_op(graph, name, signature, &spec, &mut problems);
+            op.map(|op| (op, index))
+        });
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+        // every operation was found, since there were no problems
+        let arg_locations = arg_ops.map(|op| ArgLocation::new(op.unwrap()));
+        let output_locations = output_ops.map(|op| {
+            let (op, index) = op.unwrap();
             OutputLocation::new(op, index)
         });
-        Self {
+        Ok(Self {
             arg_locations,
             output_locations,
+        })
+    }
+
+    fn output_op(
+        graph: &Graph,
+        name: &str,
+        signature: &SignatureDef,
+        spec: &TensorSpec,
+        problems: &mut Vec<SignatureProblem>,
+    ) -> Option<Operation> {
+        let Ok(info) = signature.get_output(spec.name) else {
+            problems.push(SignatureProblem::MissingOutput {
+                signature: name.to_string(),
+                output: spec.name.to_string(),
+            });
+            return None;
+        };
+        Self::check_tensor(graph, name, info, spec, problems)
+    }
+
+    // returns the operation of the tensor if it exists
+    fn check_tensor(
+        graph: &Graph,
+        name: &str,
+        info: &TensorInfo,
+        spec: &TensorSpec,
+        problems: &mut Vec<SignatureProblem>,
+    ) -> Option<Operation> {
+        if info.dtype() != spec.dtype {
+            problems.push(SignatureProblem::DataType {
+                signature: name.to_string(),
+                tensor: spec.name.to_string(),
+                expected: spec.dtype,
+                found: info.dtype(),
+            });
+        }
+        // a shape of unknown rank could be anything
+        if info.shape().dims().is_some_and(|rank| rank != spec.rank) {
+            problems.push(SignatureProblem::Shape {
+                signature: name.to_string(),
+                tensor: spec.name.to_string(),
+                expected: format!("of rank {}", spec.rank),
+                found: format!("{:?}", info.shape()),
+            });
+        }
+        match graph.operation_by_name_required(&info.name().name) {
+            Ok(op) => Some(op),
+            Err(_) => {
+                problems.push(SignatureProblem::MissingOperation {
+                    signature: name.to_string(),
+                    tensor: spec.name.to_string(),
+                });
+                None
+            }
         }
     }
 }
@@ -0,0 +1,15 @@
+use tensorflow::DataType;
+
+// What an argument or output of a function is expected to be. Only the rank
+// of its shape is checked, since batch dimensions are left unknown
+pub struct TensorSpec {
+    pub name: &'static str,
+    pub dtype: DataType,
+    pub rank: usize,
+}
+
+impl TensorSpec {
+    pub const fn new(name: &'static str, dtype: DataType, rank: usize) -> Self {
+        Self { name, dtype, rank }
+    }
+}
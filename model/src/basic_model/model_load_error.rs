@@ -0,0 +1,106 @@
+use crate::model_metadata::MetadataLoadError;
+use std::fmt;
+use std::path::PathBuf;
+use tensorflow::{DataType, Status};
+
+// Something about a function of the model that doesn't match what BasicModel
+// expects of it
+#[derive(Debug)]
+pub enum SignatureProblem {
+    MissingSignature(String),
+    MissingInput {
+        signature: String,
+        input: String,
+    },
+    MissingOutput {
+        signature: String,
+        output: String,
+    },
+    MissingOperation {
+        signature: String,
+        tensor: String,
+    },
+    DataType {
+        signature: String,
+        tensor: String,
+        expected: DataType,
+        found: DataType,
+    },
+    Shape {
+        signature: String,
+        tensor: String,
+        expected: String,
+        found: String,
+    },
+}
+
+impl fmt::Display for SignatureProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MissingSignature(signature) => write!(f, "missing function {}", signature),
+            Self::MissingInput { signature, input } => {
+                write!(f, "{} has no input {}", signature, input)
+            }
+            Self::MissingOutput { signature, output } => {
+                write!(f, "{} has no output {}", signature, output)
+            }
+            Self::MissingOperation { signature, tensor } => {
+                write!(f, "the graph has no operation for {}:{}", signature, tensor)
+            }
+            Self::DataType {
+                signature,
+                tensor,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{}:{} has dtype {} (expected {})",
+                signature, tensor, found, expected
+            ),
+            Self::Shape {
+                signature,
+                tensor,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{}:{} has shape {} (expected {})",
+                signature, tensor, found, expected
+            ),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ModelLoadError {
+    Bundle(PathBuf, Status),
+    Signatures(Vec<SignatureProblem>),
+    MetadataLoad(MetadataLoadError),
+    Metadata(String),
+}
+
+impl fmt::Display for ModelLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Bundle(path, status) => {
+                write!(
+                    f,
+                    "could not load SavedModel {}: {}",
+                    path.display(),
+                    status
+                )
+            }
+            Self::Signatures(problems) => {
+                write!(f, "the model doesn't have the expected functions:")?;
+                for problem in problems {
+                    write!(f, "\n    {}", problem)?;
+                }
+                Ok(())
+            }
+            Self::MetadataLoad(e) => write!(f, "{}", e),
+            Self::Metadata(reason) => write!(f, "invalid model metadata: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ModelLoadError {}
@@ -0,0 +1,87 @@
+use super::model_load_error::{ModelLoadError, SignatureProblem};
+use crate::StateShape;
+use tensorflow::{SavedModelBundle, Shape};
+
+// the functions that take batches of states, which must have the same shape
+// as the state of best_action
+const STATE_BATCH_INPUTS: [(&str, &str); 6] = [
+    ("train_pred_step", "states"),
+    ("train_pred_step", "new_states"),
+    ("train_pred_step_prioritized", "states"),
+    ("train_pred_step_prioritized", "new_states"),
+    ("compute_abs_td_errors", "states"),
+    ("compute_abs_td_errors", "new_states"),
+];
+
+fn known_dims(shape: &Shape) -> Option<Vec<Option<i64>>> {
+    let rank = shape.dims()?;
+    Some((0..rank).map(|dim| shape[dim]).collect())
+}
+
+impl StateShape {
+    // read from the signature of the model's best_action function. Expects
+    // the functions of the model to have been checked already
+    pub(super) fn from_model(model_bundle: &SavedModelBundle) -> Result<Self, ModelLoadError> {
+        let meta_graph_def = model_bundle.meta_graph_def();
+        let shape = meta_graph_def
+            .get_signature("best_action")
+            .and_then(|signature| signature.get_input("state"))
+            .map_err(|_| {
+                let problem = SignatureProblem::MissingInput {
+                    signature: "best_action".to_string(),
+                    input: "state".to_string(),
+                };
+                ModelLoadError::Signatures(vec![problem])
+            })?
+            .shape();
+        let dims: Option<Vec<_>> = known_dims(shape).and_then(|dims| {
+            dims.into_iter()
+                .map(|dim| dim.and_then(|dim| u64::try_from(dim).ok()))
+                .collect()
+        });
+        let state_shape = match dims
+            .and_then(|dims| <[u64; 3]>::try_from(dims).ok())
+            .and_then(Self::from_dims)
+        {
+            Some(state_shape) => state_shape,
+            None => {
+                let problem = SignatureProblem::Shape {
+                    signature: "best_action".to_string(),
+                    tensor: "state".to_string(),
+                    expected: format!(
+                        "fully known [channels, height, width] with a positive multiple of {} channels",
+                        Self::CHANNELS_PER_FRAME
+                    ),
+                    found: format!("{:?}", shape),
+                };
+                return Err(ModelLoadError::Signatures(vec![problem]));
+            }
+        };
+        let problems: Vec<_> = STATE_BATCH_INPUTS
+            .into_iter()
+            .filter_map(|(signature, input)| {
+                let shape = meta_graph_def
+                    .get_signature(signature)
+                    .and_then(|signature| signature.get_input(input))
+                    .ok()?
+                    .shape();
+                let dims = known_dims(shape)?;
+                // unknown dimensions may take any size
+                let matches = dims[1..]
+                    .iter()
+                    .zip(state_shape.dims())
+                    .all(|(dim, expected)| dim.is_none_or(|dim| dim == expected as i64));
+                (!matches).then(|| SignatureProblem::Shape {
+                    signature: signature.to_string(),
+                    tensor: input.to_string(),
+                    expected: format!("[batch, {:?}]", state_shape.dims()),
+                    found: format!("{:?}", shape),
+                })
+            })
+            .collect();
+        if !problems.is_empty() {
+            return Err(ModelLoadError::Signatures(problems));
+        }
+        Ok(state_shape)
+    }
+}
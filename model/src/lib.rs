@@ -1,8 +1,38 @@
+#[cfg(feature = "tensorflow")]
 mod basic_model;
+mod model_metadata;
+#[cfg(feature = "reference")]
+mod reference_model;
+mod state_shape;
+mod to_pixels;
 pub mod traits;
 
-pub use basic_model::BasicModel;
+#[cfg(feature = "tensorflow")]
+pub use basic_model::{BasicModel, ModelLoadError, SignatureProblem};
+pub use model_metadata::{MetadataLoadError, ModelMetadata};
+#[cfg(feature = "reference")]
+pub use reference_model::ReferenceModel;
 use serde::{Deserialize, Serialize};
+pub use state_shape::StateShape;
+pub use to_pixels::ToPixels;
+
+// the first action with the highest q value
+pub fn argmax(q_vals: &[f32]) -> usize {
+    q_vals
+        .iter()
+        .enumerate()
+        .fold(
+            (0, f32::NEG_INFINITY),
+            |(best, best_q_val), (action, q_val)| {
+                if *q_val > best_q_val {
+                    (action, *q_val)
+                } else {
+                    (best, best_q_val)
+                }
+            },
+        )
+        .0
+}
 
 pub struct LearningStepInfo {
     pub loss: f32,
@@ -0,0 +1,107 @@
+use crate::StateShape;
+use serde::Deserialize;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+
+// The settings that a model was created with, which the programs using it
+// must agree with. create_model.py writes them to metadata.json, next to the
+// SavedModel itself
+#[derive(Clone, Debug, Deserialize)]
+pub struct ModelMetadata {
+    pub n_actions: u8,
+    pub n_steps: usize,
+    pub gamma: f64,
+    // [channels, height, width]
+    pub state_shape: [u64; 3],
+}
+
+#[derive(Debug)]
+pub enum MetadataLoadError {
+    Io(PathBuf, io::Error),
+    Decode(PathBuf, serde_json::Error),
+    StateShape(PathBuf, [u64; 3]),
+    // for models that can't do without metadata
+    Missing(PathBuf),
+}
+
+impl fmt::Display for MetadataLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(path, e) => write!(f, "could not read {}: {}", path.display(), e),
+            Self::Decode(path, e) => write!(f, "could not decode {}: {}", path.display(), e),
+            Self::StateShape(path, dims) => write!(
+                f,
+                "{} has state shape {:?}, which isn't a positive number of frames of {} channels",
+                path.display(),
+                dims,
+                StateShape::CHANNELS_PER_FRAME
+            ),
+            Self::Missing(path) => write!(f, "{} doesn't exist", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for MetadataLoadError {}
+
+impl ModelMetadata {
+    const FILE_NAME: &'static str = "metadata.json";
+
+    // None if the model has no metadata file, like the models that were
+    // created before create_model.py wrote one
+    pub fn load<P: AsRef<Path>>(def_path: P) -> Result<Option<Self>, MetadataLoadError> {
+        let path = def_path.as_ref().join(Self::FILE_NAME);
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(MetadataLoadError::Io(path, e)),
+        };
+        let metadata: Self = match serde_json::from_reader(BufReader::new(file)) {
+            Ok(metadata) => metadata,
+            Err(e) => return Err(MetadataLoadError::Decode(path, e)),
+        };
+        if StateShape::from_dims(metadata.state_shape).is_none() {
+            return Err(MetadataLoadError::StateShape(path, metadata.state_shape));
+        }
+        Ok(Some(metadata))
+    }
+
+    // like load, but for models that can't fall back to legacy settings
+    pub fn load_required<P: AsRef<Path>>(def_path: P) -> Result<Self, MetadataLoadError> {
+        Self::load(&def_path)?
+            .ok_or_else(|| MetadataLoadError::Missing(def_path.as_ref().join(Self::FILE_NAME)))
+    }
+
+    // panics on a shape that isn't whole frames, which load rejects
+    pub fn state_shape(&self) -> StateShape {
+        StateShape::from_dims(self.state_shape).expect("invalid state shape in model metadata")
+    }
+
+    // the settings that create_model.py always used before it wrote them to
+    // metadata.json, which only left the state shape configurable
+    pub fn legacy(state_shape: [u64; 3]) -> Self {
+        Self {
+            n_actions: 3,
+            n_steps: 3,
+            gamma: 0.99,
+            state_shape,
+        }
+    }
+
+    // panics unless the model was created for an env with these settings
+    pub fn check_env(&self, n_actions: u8, n_steps: usize, gamma: f64) {
+        assert_eq!(
+            self.n_actions, n_actions,
+            "the model has a different number of actions than the env"
+        );
+        assert_eq!(
+            self.n_steps, n_steps,
+            "the model bootstraps over a different number of steps than the env"
+        );
+        assert_eq!(
+            self.gamma, gamma,
+            "the model has a different discount factor than the env"
+        );
+    }
+}
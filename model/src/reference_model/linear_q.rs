@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+
+// Q values that are linear in the pixels of a state, with one row of weights
+// per action
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LinearQ {
+    n_actions: usize,
+    input_len: usize,
+    weights: Vec<f32>,
+    biases: Vec<f32>,
+}
+
+impl LinearQ {
+    pub fn zeroed(n_actions: usize, input_len: usize) -> Self {
+        Self {
+            n_actions,
+            input_len,
+            weights: vec![0.0; n_actions * input_len],
+            biases: vec![0.0; n_actions],
+        }
+    }
+
+    // scales the pixels to [0, 1], like the preprocessing of create_model.py
+    pub fn inputs(&self, pixels: &[u8]) -> Vec<f32> {
+        assert_eq!(
+            pixels.len(),
+            self.input_len,
+            "state doesn't match the state length of the model"
+        );
+        pixels
+            .iter()
+            .map(|pixel| f32::from(*pixel) / 255.0)
+            .collect()
+    }
+
+    pub fn q_vals(&self, inputs: &[f32]) -> Vec<f32> {
+        self.weights
+            .chunks(self.input_len)
+            .zip(&self.biases)
+            .map(|(row, bias)| bias + row.iter().zip(inputs).map(|(w, x)| w * x).sum::<f32>())
+            .collect()
+    }
+
+    // moves the q value of the action by step, along the gradient of the q
+    // value with respect to the weights
+    pub fn step_q_val(&mut self, inputs: &[f32], action: usize, step: f32) {
+        let row = &mut self.weights[action * self.input_len..(action + 1) * self.input_len];
+        for (weight, input) in row.iter_mut().zip(inputs) {
+            *weight += step * input;
+        }
+        self.biases[action] += step;
+    }
+
+    pub fn matches_shape(&self, other: &Self) -> bool {
+        self.n_actions == other.n_actions && self.input_len == other.input_len
+    }
+}
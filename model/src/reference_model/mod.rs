@@ -0,0 +1,281 @@
+mod linear_q;
+
+use super::traits::{
+    Actor, BasicLearner, ParamFetcher, Persistable, PrioritizedLearner, TargetNet, TdErrorEstimator,
+};
+use super::LearningStepInfo;
+use crate::model_metadata::{MetadataLoadError, ModelMetadata};
+use crate::state_shape::StateShape;
+use crate::to_pixels::ToPixels;
+use crate::{argmax, Params};
+use linear_q::LinearQ;
+use replay_data::GenericTransition;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+// A small model that needs no tensorflow, so that pipelines can run where it
+// isn't available. It trains a linear Q function like create_model.py trains
+// its network: double dqn targets over n-step transitions, a Huber loss and
+// importance sampling weights. The gradients are computed by hand and applied
+// with plain SGD rather than Adam
+pub struct ReferenceModel {
+    metadata: ModelMetadata,
+    state_shape: StateShape,
+    control: LinearQ,
+    target: LinearQ,
+    gamma_pow_n: f32,
+    learning_rate: f32,
+}
+
+// a transition as the model sees it, with the td error of its q value
+struct EvaluatedTransition {
+    inputs: Vec<f32>,
+    action: usize,
+    td_error: f32,
+}
+
+impl ReferenceModel {
+    const DEFAULT_LEARNING_RATE: f32 = 0.001;
+    const CONTROL_FILE_NAME: &'static str = "control.json";
+    const TARGET_FILE_NAME: &'static str = "target.json";
+
+    // a zeroed model with the settings in the metadata.json of the model
+    // directory, which is all that a ReferenceModel takes from it
+    pub fn new<P: AsRef<Path>>(def_path: P) -> Result<Self, MetadataLoadError> {
+        ModelMetadata::load_required(def_path).map(Self::from_metadata)
+    }
+
+    // the same as new, since a ReferenceModel has no functions to leave out
+    pub fn for_inference<P: AsRef<Path>>(def_path: P) -> Result<Self, MetadataLoadError> {
+        Self::new(def_path)
+    }
+
+    pub fn from_metadata(metadata: ModelMetadata) -> Self {
+        let state_shape = metadata.state_shape();
+        let control = LinearQ::zeroed(metadata.n_actions.into(), state_shape.pixel_count());
+        Self {
+            target: control.clone(),
+            control,
+            gamma_pow_n: metadata.gamma.powi(metadata.n_steps.try_into().unwrap()) as f32,
+            learning_rate: Self::DEFAULT_LEARNING_RATE,
+            metadata,
+            state_shape,
+        }
+    }
+
+    // always true, since there is no inference-only ReferenceModel
+    pub fn can_compute_abs_td_errors(&self) -> bool {
+        true
+    }
+
+    pub fn can_compute_q_vals_batch(&self) -> bool {
+        true
+    }
+
+    pub fn metadata(&self) -> &ModelMetadata {
+        &self.metadata
+    }
+
+    pub fn state_shape(&self) -> StateShape {
+        self.state_shape
+    }
+
+    pub fn set_learning_rate(&mut self, learning_rate: f32) {
+        self.learning_rate = learning_rate;
+    }
+
+    // returns the evaluated transitions along with the average q value of the
+    // target net over the next states
+    fn evaluate<State>(
+        &self,
+        batch: &[&GenericTransition<State>],
+    ) -> (Vec<EvaluatedTransition>, f32)
+    where
+        State: ToPixels,
+    {
+        let mut q_val_sum = 0.0;
+        let mut q_val_count = 0;
+        let evaluated = batch
+            .iter()
+            .map(|transition| {
+                let inputs = self.control.inputs(&transition.state.to_pixels());
+                let next_inputs = self.control.inputs(&transition.next_state.to_pixels());
+                let target_next_q_vals = self.target.q_vals(&next_inputs);
+                q_val_sum += target_next_q_vals.iter().sum::<f32>();
+                q_val_count += target_next_q_vals.len();
+                let next_action = argmax(&self.control.q_vals(&next_inputs));
+                let done = f32::from(u8::from(transition.terminated));
+                let updated_q_val = transition.reward as f32
+                    + (1.0 - done) * self.gamma_pow_n * target_next_q_vals[next_action];
+                let action = usize::from(transition.action);
+                let td_error = updated_q_val - self.control.q_vals(&inputs)[action];
+                EvaluatedTransition {
+                    inputs,
+                    action,
+                    td_error,
+                }
+            })
+            .collect();
+        (evaluated, q_val_sum / q_val_count.max(1) as f32)
+    }
+
+    // one SGD step on the mean of the weighted Huber losses, returning the
+    // step info along with the absolute td errors before the step
+    fn train_weighted<State>(
+        &mut self,
+        batch: &[&GenericTransition<State>],
+        sample_weights: &[f32],
+    ) -> (LearningStepInfo, Vec<f64>)
+    where
+        State: ToPixels,
+    {
+        let (evaluated, average_q_val) = self.evaluate(batch);
+        let batch_len = evaluated.len().max(1) as f32;
+        let mut loss = 0.0;
+        // the gradients all refer to the params before the step, so applying
+        // them one by one is the same as applying their sum
+        for (transition, sample_weight) in evaluated.iter().zip(sample_weights) {
+            let td_error = transition.td_error;
+            loss += sample_weight * huber(td_error) / batch_len;
+            let step = self.learning_rate * sample_weight * td_error.clamp(-1.0, 1.0) / batch_len;
+            self.control
+                .step_q_val(&transition.inputs, transition.action, step);
+        }
+        let abs_td_errors = evaluated
+            .iter()
+            .map(|transition| f64::from(transition.td_error.abs()))
+            .collect();
+        (
+            LearningStepInfo {
+                loss,
+                average_q_val,
+            },
+            abs_td_errors,
+        )
+    }
+}
+
+fn huber(error: f32) -> f32 {
+    let abs_error = error.abs();
+    if abs_error <= 1.0 {
+        0.5 * abs_error * abs_error
+    } else {
+        abs_error - 0.5
+    }
+}
+
+impl<State> Actor<State> for ReferenceModel
+where
+    State: ToPixels,
+{
+    fn best_action(&self, state: &State) -> u8 {
+        let inputs = self.control.inputs(&state.to_pixels());
+        argmax(&self.control.q_vals(&inputs)) as u8
+    }
+    fn q_vals(&self, state: &State) -> Vec<f32> {
+        let inputs = self.control.inputs(&state.to_pixels());
+        self.control.q_vals(&inputs)
+    }
+}
+
+impl<State> TdErrorEstimator<GenericTransition<State>> for ReferenceModel
+where
+    State: ToPixels,
+{
+    fn compute_abs_td_errors(&self, batch: &[&GenericTransition<State>]) -> Vec<f64> {
+        let (evaluated, _) = self.evaluate(batch);
+        evaluated
+            .iter()
+            .map(|transition| f64::from(transition.td_error.abs()))
+            .collect()
+    }
+}
+
+impl<State> BasicLearner<GenericTransition<State>> for ReferenceModel
+where
+    State: ToPixels,
+{
+    fn train_batch(&mut self, batch: &[&GenericTransition<State>]) -> LearningStepInfo {
+        self.train_weighted(batch, &vec![1.0; batch.len()]).0
+    }
+}
+
+impl<State> PrioritizedLearner<GenericTransition<State>> for ReferenceModel
+where
+    State: ToPixels,
+{
+    fn train_batch_prioritized(
+        &mut self,
+        batch_transitions: &[&GenericTransition<State>],
+        batch_probabilities: &[f64],
+        min_probability: f64,
+        replay_memory_len: usize,
+        beta: f64,
+    ) -> (LearningStepInfo, Vec<f64>) {
+        let replay_memory_len = replay_memory_len as f64;
+        // assumes beta >= 0. the smallest probability corresponds to the
+        // largest sampling weight
+        let max_sample_weight = (replay_memory_len * min_probability).powf(-beta);
+        let sample_weights = batch_probabilities
+            .iter()
+            .map(|probability| {
+                ((replay_memory_len * probability).powf(-beta) / max_sample_weight) as f32
+            })
+            .collect::<Vec<_>>();
+        self.train_weighted(batch_transitions, &sample_weights)
+    }
+}
+
+impl TargetNet for ReferenceModel {
+    fn copy_control_to_target(&mut self) {
+        self.target = self.control.clone();
+    }
+}
+
+impl ReferenceModel {
+    fn save_net(net: &LinearQ, filepath: &Path) {
+        let file = BufWriter::new(File::create(filepath).unwrap());
+        serde_json::to_writer(file, net).unwrap();
+    }
+    fn load_net(net: &mut LinearQ, filepath: &Path) {
+        let file = BufReader::new(File::open(filepath).unwrap());
+        let loaded: LinearQ = serde_json::from_reader(file).unwrap();
+        assert!(
+            loaded.matches_shape(net),
+            "saved net in {} doesn't match the shape of the model",
+            filepath.display()
+        );
+        *net = loaded;
+    }
+}
+
+impl Persistable for ReferenceModel {
+    fn save<P: AsRef<Path>>(&self, path: P) {
+        let path = path.as_ref();
+        fs::create_dir_all(path).unwrap();
+        Self::save_net(&self.control, &path.join(Self::CONTROL_FILE_NAME));
+        Self::save_net(&self.target, &path.join(Self::TARGET_FILE_NAME));
+    }
+    fn load<P: AsRef<Path>>(&mut self, path: P) {
+        let path = path.as_ref();
+        Self::load_net(&mut self.control, &path.join(Self::CONTROL_FILE_NAME));
+        Self::load_net(&mut self.target, &path.join(Self::TARGET_FILE_NAME));
+    }
+}
+
+impl ParamFetcher for ReferenceModel {
+    fn params(&self) -> Params {
+        Params(vec![serde_json::to_string(&self.control).unwrap()])
+    }
+    fn set_params(&mut self, params: Params) {
+        let [control] = <[String; 1]>::try_from(params.0)
+            .unwrap_or_else(|_| panic!("params don't belong to a reference model"));
+        let control: LinearQ = serde_json::from_str(&control).unwrap();
+        assert!(
+            control.matches_shape(&self.control),
+            "params don't match the shape of the model"
+        );
+        self.control = control;
+    }
+}
@@ -0,0 +1,35 @@
+// The shape of a single state as the model takes it. Each frame of a state
+// takes up two channels
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StateShape {
+    pub channels: u64,
+    pub height: u64,
+    pub width: u64,
+}
+
+impl StateShape {
+    pub const CHANNELS_PER_FRAME: u64 = 2;
+
+    // None unless there is a positive number of whole frames
+    pub fn from_dims([channels, height, width]: [u64; 3]) -> Option<Self> {
+        (channels > 0 && channels.is_multiple_of(Self::CHANNELS_PER_FRAME)).then_some(Self {
+            channels,
+            height,
+            width,
+        })
+    }
+    pub fn frame_stack_len(&self) -> usize {
+        (self.channels / Self::CHANNELS_PER_FRAME) as usize
+    }
+    pub fn pixel_count(&self) -> usize {
+        (self.channels * self.height * self.width) as usize
+    }
+    #[cfg(feature = "tensorflow")]
+    pub(crate) fn dims(&self) -> [u64; 3] {
+        [self.channels, self.height, self.width]
+    }
+    #[cfg(feature = "tensorflow")]
+    pub(crate) fn batch_dims(&self, batch_len: u64) -> [u64; 4] {
+        [batch_len, self.channels, self.height, self.width]
+    }
+}
@@ -1,4 +1,5 @@
 use std::rc::Rc;
+use std::sync::Arc;
 
 use image::{ImageOwned, ImageOwned2, ImageRef2};
 use replay_data::{CompressedImageOwned2, GenericState};
@@ -40,6 +41,15 @@ where
     }
 }
 
+impl<I> ToPixels for Arc<I>
+where
+    I: ToPixels,
+{
+    fn to_pixels(&self) -> Vec<u8> {
+        self.as_ref().to_pixels()
+    }
+}
+
 impl<I> ToPixels for GenericState<I>
 where
     I: ToPixels,
@@ -3,6 +3,11 @@ use std::path::Path;
 
 pub trait Actor<State> {
     fn best_action(&self, state: &State) -> u8;
+    // the q value of every action, indexed by action
+    fn q_vals(&self, state: &State) -> Vec<f32>;
+    fn q_vals_batch(&self, states: &[&State]) -> Vec<Vec<f32>> {
+        states.iter().map(|state| self.q_vals(state)).collect()
+    }
 }
 
 pub trait BasicLearner<Transition> {
@@ -20,6 +25,12 @@ pub trait PrioritizedLearner<Transition> {
     ) -> (LearningStepInfo, Vec<f64>);
 }
 
+// the absolute td errors of transitions under the current params, which serve
+// as their initial priorities
+pub trait TdErrorEstimator<Transition> {
+    fn compute_abs_td_errors(&self, batch: &[&Transition]) -> Vec<f64>;
+}
+
 pub trait TargetNet {
     fn copy_control_to_target(&mut self);
 }
@@ -1,40 +1,31 @@
-use replay_data::{CompressedImageOwned2, CompressedRcState};
+use replay_data::{CompressedArcState, CompressedImageOwned2};
 use std::collections::VecDeque;
-use std::rc::Rc;
+use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct FrameStack {
-    stack: VecDeque<Rc<CompressedImageOwned2>>,
+    stack: VecDeque<Arc<CompressedImageOwned2>>,
 }
 
 impl FrameStack {
+    // starts out as len copies of the frame
+    pub fn new(frame: CompressedImageOwned2, len: usize) -> Self {
+        assert!(len > 0, "frame stack len should be positive");
+        Self {
+            stack: std::iter::repeat_n(Arc::new(frame), len).collect(),
+        }
+    }
     pub fn push(&mut self, frame: CompressedImageOwned2) {
         self.stack.pop_front();
-        self.stack.push_back(Rc::new(frame));
+        self.stack.push_back(Arc::new(frame));
     }
 
-    pub fn as_state(&self) -> CompressedRcState {
-        <[Rc<CompressedImageOwned2>; 4]>::try_from(Vec::from(self.stack.clone()))
-            .expect("frame stack len should be 4")
-            .into()
+    pub fn as_state(&self) -> CompressedArcState {
+        Vec::from(self.stack.clone()).into()
     }
     pub fn reset_to_current(&mut self) {
+        let len = self.stack.len();
         let frame = self.stack.pop_back().unwrap();
-        *self = Self::from(frame);
-    }
-}
-
-impl From<CompressedImageOwned2> for FrameStack {
-    fn from(frame: CompressedImageOwned2) -> Self {
-        let frame = Rc::new(frame);
-        Self::from(frame)
-    }
-}
-
-impl From<Rc<CompressedImageOwned2>> for FrameStack {
-    fn from(frame: Rc<CompressedImageOwned2>) -> Self {
-        Self {
-            stack: VecDeque::from([frame.clone(), frame.clone(), frame.clone(), frame]),
-        }
+        self.stack = std::iter::repeat_n(frame, len).collect();
     }
 }
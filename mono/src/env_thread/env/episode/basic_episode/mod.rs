@@ -3,10 +3,13 @@ mod step_memory;
 
 use super::{Done, Status};
 use frame_stack::FrameStack;
-use replay_data::{CompressedImageOwned2, CompressedRcState, CompressedRcTransition};
+use replay_data::{CompressedArcState, CompressedArcTransition, CompressedImageOwned2};
 use std::collections::VecDeque;
 use step_memory::StepMemory;
 
+pub const N_STEPS: usize = 3;
+pub const GAMMA: f64 = 0.99;
+
 pub struct BasicEpisode {
     step_memory: StepMemory,
     state: FrameStack,
@@ -14,12 +17,10 @@ pub struct BasicEpisode {
 }
 
 impl BasicEpisode {
-    pub fn new(frame: CompressedImageOwned2, score: u32) -> Self {
-        const N_STEPS: usize = 3;
-        const GAMMA: f64 = 0.99;
+    pub fn new(frame: CompressedImageOwned2, score: u32, frame_stack_len: usize) -> Self {
         Self {
             step_memory: StepMemory::new(N_STEPS, GAMMA),
-            state: FrameStack::from(frame),
+            state: FrameStack::new(frame, frame_stack_len),
             score,
         }
     }
@@ -28,7 +29,7 @@ impl BasicEpisode {
         action: u8,
         next_frame: CompressedImageOwned2,
         next_score: u32,
-        transition_queue: &mut VecDeque<(CompressedRcTransition, Option<u32>)>,
+        transition_queue: &mut VecDeque<(CompressedArcTransition, Option<u32>)>,
     ) -> Status {
         let state = self.state.as_state();
         let score = self.score;
@@ -71,7 +72,7 @@ impl BasicEpisode {
         const TERMINATION_SCORE_THRESHOLD: u32 = 10;
         score >= next_score + TERMINATION_SCORE_THRESHOLD
     }
-    pub fn state(&self) -> CompressedRcState {
+    pub fn state(&self) -> CompressedArcState {
         self.state.as_state()
     }
     pub fn score(&self) -> u32 {
@@ -2,6 +2,6 @@ mod basic_episode;
 mod status;
 mod time_limited_wrapper;
 
-pub use basic_episode::BasicEpisode;
+pub use basic_episode::{BasicEpisode, GAMMA, N_STEPS};
 pub use status::{Done, Status};
 pub use time_limited_wrapper::TimeLimitedWrapper;
@@ -4,23 +4,26 @@ mod message_bridge;
 use super::EnvThreadMessage;
 use crate::GameThreadMessage;
 use crossbeam_channel::{Receiver, Sender};
-use episode::{BasicEpisode, Done, Status, TimeLimitedWrapper};
+use episode::{BasicEpisode, Done, Status, TimeLimitedWrapper, GAMMA, N_STEPS};
 pub use message_bridge::StepError;
 use message_bridge::{MessageBridge, Reply, Request};
-use replay_data::{CompressedImageOwned2, CompressedRcState, CompressedRcTransition};
+use replay_data::{CompressedArcState, CompressedArcTransition, CompressedImageOwned2};
 use std::collections::VecDeque;
 
 pub struct Env {
     bridge: MessageBridge,
     episode: TimeLimitedWrapper,
-    pending_transitions: VecDeque<(CompressedRcTransition, Option<u32>)>,
+    pending_transitions: VecDeque<(CompressedArcTransition, Option<u32>)>,
     waiting_hold: bool,
+    // the number of frames in a state
+    frame_stack_len: usize,
 }
 
 impl Env {
     pub fn new(
         receiver: Receiver<EnvThreadMessage>,
         game_thread_sender: Sender<GameThreadMessage>,
+        frame_stack_len: usize,
     ) -> Result<Self, StepError> {
         let (bridge, reply) = MessageBridge::new(receiver, game_thread_sender)?;
         let Reply {
@@ -30,9 +33,14 @@ impl Env {
         } = reply;
         Ok(Self {
             bridge,
-            episode: TimeLimitedWrapper::new(BasicEpisode::new((&frame).into(), score)),
+            episode: TimeLimitedWrapper::new(BasicEpisode::new(
+                (&frame).into(),
+                score,
+                frame_stack_len,
+            )),
             pending_transitions: VecDeque::new(),
             waiting_hold: received_wait_for_hold,
+            frame_stack_len,
         })
     }
     pub fn step(&mut self, action: u8) -> Result<(), StepError> {
@@ -59,7 +67,8 @@ impl Env {
     }
     fn truncate(&mut self) -> Result<(), StepError> {
         let (frame, score) = self.send(Request::Truncation)?;
-        self.episode = TimeLimitedWrapper::new(BasicEpisode::new(frame, score));
+        self.episode =
+            TimeLimitedWrapper::new(BasicEpisode::new(frame, score, self.frame_stack_len));
         Ok(())
     }
     fn send(&mut self, request: Request) -> Result<(CompressedImageOwned2, u32), StepError> {
@@ -73,13 +82,19 @@ impl Env {
         }
         Ok(((&frame).into(), score))
     }
-    pub fn state(&self) -> CompressedRcState {
+    pub fn state(&self) -> CompressedArcState {
         self.episode.state()
     }
-    pub fn pop_transition(&mut self) -> Option<(CompressedRcTransition, Option<u32>)> {
+    pub fn pop_transition(&mut self) -> Option<(CompressedArcTransition, Option<u32>)> {
         self.pending_transitions.pop_front()
     }
     pub const fn n_actions() -> u8 {
         MessageBridge::n_actions()
     }
+    pub const fn n_steps() -> usize {
+        N_STEPS
+    }
+    pub const fn gamma() -> f64 {
+        GAMMA
+    }
 }
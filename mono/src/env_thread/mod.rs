@@ -3,17 +3,18 @@ mod plot_datum_sender;
 mod training_schedule;
 
 use crate::{
-    GameThreadMessage, MasterMessage, MasterThreadMessage, PlotThreadMessage, ThreadId,
+    GameThreadMessage, MasterMessage, MasterThreadMessage, Model, PlotThreadMessage, ThreadId,
     UiThreadMessage,
 };
 use crossbeam_channel::{Receiver, Sender};
 use env::{Env, StepError};
+use file_io::Manifest;
 use image::ImageOwned2;
 use model::traits::{Actor, Persistable, TargetNet};
-use model::BasicModel;
 use plot_datum_sender::PlotDatumSender;
 use rand::Rng;
 use replay_data::State;
+use replay_memories::{Prioritization, ReplayPrioritized, ReplayShared};
 use replay_wrappers::PrioritizedReplayWrapper;
 use training_schedule::TrainingSchedule;
 
@@ -26,7 +27,7 @@ const THREAD_NAME: &str = "env";
 
 fn step(
     env: &mut Env,
-    agent: &mut PrioritizedReplayWrapper<BasicModel>,
+    agent: &mut PrioritizedReplayWrapper<Model, ReplayShared>,
     schedule: &mut TrainingSchedule,
     master_thread_sender: &Sender<MasterThreadMessage>,
     ui_thread_sender: &Sender<UiThreadMessage>,
@@ -68,6 +69,22 @@ fn step(
         agent.remember(transition);
     }
     if !schedule.is_on_eps_random() {
+        // changing alpha rebuilds the memory's priorities, so it is only
+        // changed every few steps
+        const ALPHA_START: f64 = 0.6;
+        const ALPHA_END: f64 = 0.4;
+        const N_ALPHA_ANNEALING_FRAMES: u32 = 2_000_000;
+        const ALPHA_UPDATE_INTERVAL_STEPS: u32 = 10_000;
+        if schedule
+            .n_step()
+            .is_multiple_of(ALPHA_UPDATE_INTERVAL_STEPS)
+        {
+            let alpha = ALPHA_START
+                + (ALPHA_END - ALPHA_START) * f64::from(schedule.n_step())
+                    / f64::from(N_ALPHA_ANNEALING_FRAMES);
+            let alpha = if alpha < ALPHA_END { ALPHA_END } else { alpha };
+            agent.set_alpha(alpha);
+        }
         const BETA_START: f64 = 0.4;
         const BETA_END: f64 = 1.0;
         const N_BETA_ANNEALING_FRAMES: u32 = 2_000_000;
@@ -145,24 +162,59 @@ pub fn spawn_env_thread(
             N_EPS_GREEDY_STEPS,
             TARGET_UPDATE_INTERVAL_STEPS,
         );
-        const ALPHA: f64 = 0.6;
-        let mut agent =
-            PrioritizedReplayWrapper::wrap(BasicModel::new("model"), MEMORY_CAPACITY, ALPHA);
+        const INITIAL_ALPHA: f64 = 0.6;
+        const PRIORITIZATION: Prioritization = Prioritization::Proportional;
+        let model = match Model::new("model") {
+            Ok(model) => model,
+            Err(e) => panic!("{THREAD_NAME} thread: could not load model: {}", e),
+        };
+        // the model must have been created for the same env that this thread runs
+        model
+            .metadata()
+            .check_env(Env::n_actions(), Env::n_steps(), Env::gamma());
+        // the env stacks as many frames as the model takes
+        let frame_stack_len = model.state_shape().frame_stack_len();
+        let memory = ReplayShared::new(ReplayPrioritized::with_prioritization(
+            MEMORY_CAPACITY,
+            PRIORITIZATION,
+            INITIAL_ALPHA,
+        ));
+        let mut agent = PrioritizedReplayWrapper::wrap_memory(model, memory);
         let mut mode = ThreadMode::Held;
         loop {
             match mode {
                 ThreadMode::Held => match receiver.recv().unwrap() {
                     EnvThreadMessage::Master(message) => match message {
                         MasterMessage::Save(path) => {
-                            schedule.save(path.as_path());
-                            agent.save(path);
+                            let mut manifest = Manifest::new();
+                            manifest.add_config("memory_capacity", MEMORY_CAPACITY);
+                            manifest.add_config("prioritization", format!("{:?}", PRIORITIZATION));
+                            schedule.save(path.as_path(), &mut manifest);
+                            agent.save_concurrently(path.as_path());
+                            manifest.save(path).unwrap();
                             master_thread_sender
                                 .send(MasterThreadMessage::Done(THREAD_ID))
                                 .unwrap();
                         }
                         MasterMessage::Load(path) => {
-                            schedule.load(path.as_path());
-                            agent.load(path);
+                            // the schedule is only replaced once the agent has loaded
+                            // too, so that a failed load keeps all of the current state
+                            let load_result = Manifest::load(path.as_path())
+                                .and_then(|manifest| {
+                                    TrainingSchedule::from_saved(path.as_path(), &manifest)
+                                })
+                                .and_then(|loaded_schedule| {
+                                    agent.load(path.as_path())?;
+                                    Ok(loaded_schedule)
+                                });
+                            match load_result {
+                                Ok(loaded_schedule) => schedule = loaded_schedule,
+                                Err(e) => eprintln!(
+                                    "{THREAD_NAME} thread: could not load {}: {}",
+                                    path.display(),
+                                    e
+                                ),
+                            }
                             master_thread_sender
                                 .send(MasterThreadMessage::Done(THREAD_ID))
                                 .unwrap();
@@ -171,7 +223,11 @@ pub fn spawn_env_thread(
                             eprintln!("{THREAD_NAME} thread: {:?} while already held", message);
                         }
                         MasterMessage::Resume => {
-                            match Env::new(receiver.clone(), game_thread_sender.clone()) {
+                            match Env::new(
+                                receiver.clone(),
+                                game_thread_sender.clone(),
+                                frame_stack_len,
+                            ) {
                                 Ok(env) => {
                                     mode = ThreadMode::Running(env);
                                 }
@@ -1,4 +1,4 @@
-use file_io::{create_file_buf_write, has_data_left, open_file_buf_read};
+use file_io::{create_file_buf_write, load_bincode, LoadError, Manifest};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 
@@ -13,6 +13,9 @@ pub struct TrainingSchedule {
 }
 
 impl TrainingSchedule {
+    const COMPONENT: &'static str = "schedule";
+    const FORMAT_VERSION: u32 = 1;
+
     pub fn new(
         eps_min: f64,
         eps_max: f64,
@@ -51,16 +54,16 @@ impl TrainingSchedule {
     pub fn is_time_to_update_target(&self) -> bool {
         (self.n_step - self.n_eps_random_steps) % self.target_update_interval_steps == 0
     }
-    pub fn save<P: AsRef<Path>>(&self, path: P) {
+    pub fn save<P: AsRef<Path>>(&self, path: P, manifest: &mut Manifest) {
         let file = create_file_buf_write(path.as_ref().join("schedule")).unwrap();
         bincode::serialize_into(file, self).unwrap();
+        manifest.add_component(Self::COMPONENT, Self::FORMAT_VERSION);
     }
-    pub fn load<P: AsRef<Path>>(&mut self, path: P) {
-        let mut file = open_file_buf_read(path.as_ref().join("schedule")).unwrap();
-        *self = bincode::deserialize_from(&mut file).unwrap();
-        assert!(
-            !has_data_left(file).unwrap(),
-            "deserialization of file didn't reach EOF"
-        );
+    // returns the saved schedule rather than loading it in place, so that a
+    // checkpoint whose other components fail to load leaves the current one
+    // untouched
+    pub fn from_saved<P: AsRef<Path>>(path: P, manifest: &Manifest) -> Result<Self, LoadError> {
+        manifest.check_component(Self::COMPONENT, Self::FORMAT_VERSION)?;
+        load_bincode(path.as_ref().join("schedule"))
     }
 }
@@ -20,6 +20,14 @@ use plot_thread::{spawn_plot_thread, PlotThreadMessage, PlotType};
 use ui_thread::{spawn_ui_thread, UiThreadMessage};
 use x11_utils::{choose_matching_fbconfigs, GlxContext, Window, X11Display};
 
+// the model backend, chosen by the crate's features
+#[cfg(feature = "tensorflow")]
+type Model = model::BasicModel;
+#[cfg(all(feature = "reference", not(feature = "tensorflow")))]
+type Model = model::ReferenceModel;
+#[cfg(not(any(feature = "tensorflow", feature = "reference")))]
+compile_error!("a model backend must be enabled with the tensorflow or reference feature");
+
 // # Rationale for enabling jemalloc
 //
 // The program is very demanding in terms of RAM usage, typically requiring
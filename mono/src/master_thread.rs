@@ -1,7 +1,7 @@
 use crate::{spawn_env_thread, spawn_game_thread, spawn_plot_thread, spawn_ui_thread};
 use crate::{EnvThreadMessage, GameThreadMessage, PlotThreadMessage, UiThreadMessage};
 use crossbeam_channel::{Receiver, Sender};
-use std::fs;
+use file_io::checkpoint;
 use std::path::PathBuf;
 use std::thread::JoinHandle;
 
@@ -95,13 +95,29 @@ enum ThreadMode {
 
 fn save(master_sender: &MasterSender, receiver: &Receiver<MasterThreadMessage>) {
     let saved_path = "saved";
-    fs::create_dir_all(saved_path).unwrap();
-    master_sender.send_all(MasterMessage::Save(saved_path.into()));
+    // the threads save into a staging directory, which replaces the checkpoint
+    // at saved_path only once all of them are done
+    let staging_path = match checkpoint::begin(saved_path) {
+        Ok(staging_path) => staging_path,
+        Err(e) => {
+            eprintln!("could not save: {}", e);
+            return;
+        }
+    };
+    master_sender.send_all(MasterMessage::Save(staging_path));
     wait_all_done(receiver);
+    if let Err(e) = checkpoint::commit(saved_path) {
+        eprintln!("could not save: {}", e);
+    }
 }
 
 fn load(master_sender: &MasterSender, receiver: &Receiver<MasterThreadMessage>) {
-    master_sender.send_all(MasterMessage::Load("load".into()));
+    let load_path = "load";
+    if let Err(e) = checkpoint::verify(load_path) {
+        eprintln!("could not load: {}", e);
+        return;
+    }
+    master_sender.send_all(MasterMessage::Load(load_path.into()));
     wait_all_done(receiver);
 }
 
@@ -90,6 +90,13 @@ impl Loop {
         self.plots.save(plots_path);
     }
     fn load<P: AsRef<Path>>(&mut self, path: P) {
-        self.plots.load(path.as_ref().join("plots"));
+        let plots_path = path.as_ref().join("plots");
+        if let Err(e) = self.plots.load(&plots_path) {
+            eprintln!(
+                "plot thread: could not load {}: {}",
+                plots_path.display(),
+                e
+            );
+        }
     }
 }
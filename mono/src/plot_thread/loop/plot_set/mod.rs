@@ -1,5 +1,6 @@
 mod plot;
 
+use file_io::{LoadError, Manifest};
 use plot::Plot;
 use std::path::Path;
 
@@ -29,15 +30,19 @@ impl PlotSet {
     }
     pub fn save<P: AsRef<Path>>(&self, path: P) {
         let path = path.as_ref();
+        let mut manifest = Manifest::new();
         for plot in self.plots() {
-            plot.save(path.join(plot.fs_name()));
+            plot.save(path.join(plot.fs_name()), &mut manifest);
         }
+        manifest.save(path).unwrap();
     }
-    pub fn load<P: AsRef<Path>>(&mut self, path: P) {
+    pub fn load<P: AsRef<Path>>(&mut self, path: P) -> Result<(), LoadError> {
         let path = path.as_ref();
+        let manifest = Manifest::load(path)?;
         for plot in self.plots_mut() {
-            plot.load(path.join(plot.fs_name()));
+            plot.load(path.join(plot.fs_name()), &manifest)?;
         }
+        Ok(())
     }
     fn plot_mut(&mut self, plot_type: PlotType) -> &mut Plot {
         match plot_type {
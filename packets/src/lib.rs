@@ -1,6 +1,7 @@
 use model::Params;
-use replay_data::CompressedTransition;
+use replay_data::{CompressedTransition, TransitionFilter, TransitionMetadata};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::net::SocketAddr;
 
 #[derive(Serialize, Deserialize)]
@@ -76,7 +77,9 @@ pub struct GetParamsReply {
 #[derive(Serialize, Deserialize)]
 pub struct Insertion {
     pub transition: CompressedTransition,
-    pub priority: f64,
+    // may be left for the replay server to assign
+    pub priority: Option<f64>,
+    pub metadata: Option<TransitionMetadata>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -91,8 +94,35 @@ pub enum ReplayRequest {
     SampleBatch { batch_len: usize },
     InsertBatch { batch: Vec<Insertion> },
     UpdateBatchPriorities { batch: Vec<PriorityUpdate> },
+    SetAlpha { alpha: f64 },
+    Stats,
+    // replied to with the number of transitions in memory
+    Len,
+    // replied to with the matching transitions and their metadata, from
+    // oldest to newest
+    FetchTransitions { filter: TransitionFilter },
+    // replied to with the number of deleted transitions
+    DeleteTransitions { filter: TransitionFilter },
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AgeBucket {
+    // None for the last bucket, which holds everything older
+    pub max_age_secs: Option<u64>,
+    pub transition_count: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReplayStats {
+    pub transition_count: usize,
+    pub without_metadata_count: usize,
+    pub per_actor_counts: BTreeMap<usize, usize>,
+    // only counts transitions with metadata
+    pub age_buckets: Vec<AgeBucket>,
 }
 
+pub type FetchTransitionsReply = Vec<(CompressedTransition, TransitionMetadata)>;
+
 #[derive(Serialize, Deserialize)]
 pub struct SampleBatchReply {
     pub batch: (Vec<usize>, Vec<f64>, Vec<CompressedTransition>),
@@ -103,10 +133,18 @@ pub struct SampleBatchReply {
 #[derive(Serialize, Deserialize)]
 pub enum SampleBatchErrorKind {
     NotEnoughTransitions,
+    RateLimited,
 }
 
 pub type SampleBatchResult = Result<SampleBatchReply, SampleBatchErrorKind>;
 
+#[derive(Serialize, Deserialize)]
+pub enum InsertBatchErrorKind {
+    RateLimited,
+}
+
+pub type InsertBatchResult = Result<(), InsertBatchErrorKind>;
+
 #[derive(Serialize, Deserialize, Copy, Clone)]
 pub enum LearnerPlotKind {
     QVal,
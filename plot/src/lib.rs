@@ -0,0 +1,3 @@
+mod plot_set;
+
+pub use plot_set::{Plot, PlotSet};
@@ -1,9 +1,7 @@
-mod plot_set;
-
 use coordinator_client::CoordinatorClient;
 use local_ip_address::local_ip;
 use packets::{PlotRequest, PlotSettings};
-use plot_set::PlotSet;
+use plot::PlotSet;
 use prompt::prompt_user_for_service_ip_addr;
 use std::net::{Ipv4Addr, TcpListener};
 
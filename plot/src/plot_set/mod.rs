@@ -1,7 +1,8 @@
 mod plot;
 
+use file_io::{LoadError, Manifest};
 use packets::{LearnerPlotKind, PlotKind};
-use plot::Plot;
+pub use plot::Plot;
 use std::path::Path;
 
 pub struct PlotSet {
@@ -28,15 +29,19 @@ impl PlotSet {
     }
     pub fn save<P: AsRef<Path>>(&self, path: P) {
         let path = path.as_ref();
+        let mut manifest = Manifest::new();
         for plot in self.plots() {
-            plot.save(path.join(plot.fs_name()));
+            plot.save(path.join(plot.fs_name()), &mut manifest);
         }
+        manifest.save(path).unwrap();
     }
-    pub fn load<P: AsRef<Path>>(&mut self, path: P) {
+    pub fn load<P: AsRef<Path>>(&mut self, path: P) -> Result<(), LoadError> {
         let path = path.as_ref();
+        let manifest = Manifest::load(path)?;
         for plot in self.plots_mut() {
-            plot.load(path.join(plot.fs_name()));
+            plot.load(path.join(plot.fs_name()), &manifest)?;
         }
+        Ok(())
     }
     fn plot_mut(&mut self, kind: PlotKind) -> &mut Plot {
         match kind {
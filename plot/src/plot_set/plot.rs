@@ -1,4 +1,4 @@
-use file_io::{create_file_buf_write, has_data_left, open_file_buf_read};
+use file_io::{create_file_buf_write, load_bincode, LoadError, Manifest};
 use serde::{Deserialize, Serialize};
 use std::{
     fs,
@@ -16,6 +16,9 @@ pub struct Plot {
 }
 
 impl Plot {
+    const COMPONENT: &'static str = "plot";
+    const FORMAT_VERSION: u32 = 1;
+
     pub fn new(output_path: PathBuf, fs_name: PathBuf, data_per_point: usize) -> Self {
         Self {
             points: vec![],
@@ -59,17 +62,15 @@ impl Plot {
                 .unwrap();
         serde_json::to_writer(file, self).unwrap();
     }
-    pub fn save<P: AsRef<Path>>(&self, path: P) {
+    pub fn save<P: AsRef<Path>>(&self, path: P, manifest: &mut Manifest) {
         let file = create_file_buf_write(path).unwrap();
         bincode::serialize_into(file, self).unwrap();
+        manifest.add_component(Self::COMPONENT, Self::FORMAT_VERSION);
     }
-    pub fn load<P: AsRef<Path>>(&mut self, path: P) {
-        let mut file = open_file_buf_read(path).unwrap();
-        *self = bincode::deserialize_from(&mut file).unwrap();
-        assert!(
-            !has_data_left(file).unwrap(),
-            "deserialization of file didn't reach EOF"
-        );
+    pub fn load<P: AsRef<Path>>(&mut self, path: P, manifest: &Manifest) -> Result<(), LoadError> {
+        manifest.check_component(Self::COMPONENT, Self::FORMAT_VERSION)?;
+        *self = load_bincode(path)?;
+        Ok(())
     }
     pub fn fs_name(&self) -> &PathBuf {
         &self.fs_name
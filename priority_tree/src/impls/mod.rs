@@ -5,6 +5,18 @@ use super::MinTree;
 use super::SumTree;
 pub use super::{Priority, PriorityTree};
 
+// Every update recomputes the ancestors of its leaves from their children, so
+// the total should only differ from the sum of the leaves by the rounding of
+// the sums. Every so many updates the two are compared, and the tree is
+// rebuilt from its leaves if they have drifted further apart than rounding
+// explains. Both cost less than one extra node update per priority update,
+// amortized
+const DRIFT_CHECK_INTERVAL: usize = 1 << 24;
+// relative to the total. Rounding of a sum of n non-negative priorities stays
+// below n times the machine epsilon, far below this for any leaf count that
+// fits in memory
+const MAX_DRIFT: f64 = 1e-6;
+
 impl<P: Priority> PriorityTree<P> {
     pub fn with_leaf_count(leaf_count: usize) -> Self {
         let sum_tree = SumTree::with_leaf_count(leaf_count);
@@ -14,7 +26,43 @@ impl<P: Priority> PriorityTree<P> {
             min_tree: MinTree::with_leaf_count(leaf_count),
             max_tree: MaxTree::with_leaf_count(leaf_count),
             first_leaf,
+            updates_since_rebuild: 0,
+        }
+    }
+
+    // builds a tree whose first leaves hold the given priorities. Every node
+    // is computed once, which is much cheaper than updating the priorities
+    // one by one. None leaves the corresponding leaf reset
+    pub fn with_priorities<I>(leaf_count: usize, priorities: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Option<P>>,
+    {
+        let mut tree = Self::with_leaf_count(leaf_count);
+        for (index, priority) in priorities.into_iter().take(leaf_count).enumerate() {
+            if let Some(priority) = priority.into() {
+                let leaf = tree.first_leaf + index;
+                tree.sum_tree.set_leaf_value(leaf, priority);
+                tree.min_tree.set_leaf_value(leaf, priority.into());
+                tree.max_tree.set_leaf_value(leaf, priority.into());
+            }
         }
+        tree.sum_tree.rebuild();
+        tree.min_tree.rebuild();
+        tree.max_tree.rebuild();
+        tree
+    }
+
+    pub fn leaf_count(&self) -> usize {
+        self.sum_tree.leaf_count()
+    }
+
+    // changes the number of leaves, keeping the priorities at the indices
+    // that still fit
+    pub fn resize(&mut self, leaf_count: usize) {
+        let kept_count = leaf_count.min(self.leaf_count());
+        let priorities: Vec<_> = (0..kept_count).map(|index| self.priority(index)).collect();
+        *self = Self::with_priorities(leaf_count, priorities);
     }
 
     pub fn min(&self) -> Option<P> {
@@ -39,6 +87,7 @@ impl<P: Priority> PriorityTree<P> {
         self.sum_tree.update_value(leaf, priority);
         self.min_tree.update_value(leaf, priority.into());
         self.max_tree.update_value(leaf, priority.into());
+        self.count_updates(1);
     }
 
     pub fn reset(&mut self, index: usize) {
@@ -46,5 +95,112 @@ impl<P: Priority> PriorityTree<P> {
         self.sum_tree.reset_value(leaf);
         self.min_tree.reset_value(leaf);
         self.max_tree.reset_value(leaf);
+        self.count_updates(1);
+    }
+
+    // same as calling update for each of the given (index, priority) pairs,
+    // except that each affected ancestor is only recomputed once
+    pub fn update_many(&mut self, priorities: &[(usize, P)]) {
+        let first_leaf = self.first_leaf;
+        let leaves = priorities
+            .iter()
+            .map(move |(index, priority)| (first_leaf + index, *priority));
+        self.sum_tree.update_values(leaves.clone());
+        self.min_tree.update_values(
+            leaves
+                .clone()
+                .map(|(leaf, priority)| (leaf, priority.into())),
+        );
+        self.max_tree
+            .update_values(leaves.map(|(leaf, priority)| (leaf, priority.into())));
+        self.count_updates(priorities.len());
+    }
+
+    // same as calling reset for each of the given indices, except that each
+    // affected ancestor is only recomputed once
+    pub fn reset_many(&mut self, indices: &[usize]) {
+        let first_leaf = self.first_leaf;
+        let leaves = indices.iter().map(move |index| first_leaf + index);
+        self.sum_tree.reset_values(leaves.clone());
+        self.min_tree.reset_values(leaves.clone());
+        self.max_tree.reset_values(leaves);
+        self.count_updates(indices.len());
+    }
+
+    fn count_updates(&mut self, update_count: usize) {
+        self.updates_since_rebuild += update_count;
+        if self.updates_since_rebuild >= DRIFT_CHECK_INTERVAL {
+            self.rebuild_if_drifted();
+            self.updates_since_rebuild = 0;
+        }
+    }
+
+    // returns how far the total priority has drifted from the actual sum of
+    // the priorities
+    pub fn drift(&self) -> P {
+        let priority_sum = (0..self.leaf_count())
+            .map(|index| self.priority(index))
+            .fold(P::zero(), |sum, priority| sum + priority);
+        self.total() - priority_sum
+    }
+
+    // returns whether the drift was large enough for the tree to be rebuilt
+    pub fn rebuild_if_drifted(&mut self) -> bool {
+        let drift = self.drift();
+        let abs_drift = if drift < P::zero() {
+            P::zero() - drift
+        } else {
+            drift
+        };
+        let drifted = abs_drift > self.total() * P::from_f64(MAX_DRIFT);
+        if drifted {
+            self.rebuild();
+        }
+        drifted
+    }
+
+    pub fn rebuild(&mut self) {
+        self.sum_tree.rebuild();
+        self.updates_since_rebuild = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summed_leaves(tree: &PriorityTree<f64>) -> f64 {
+        (0..tree.leaf_count())
+            .map(|index| tree.priority(index))
+            .sum()
+    }
+
+    #[test]
+    fn rebuild_removes_drift() {
+        const LEAF_COUNT: usize = 1000;
+        let mut tree = PriorityTree::<f64>::with_leaf_count(LEAF_COUNT);
+        // priorities of very different magnitudes, so that the sums round
+        for round in 0..100 {
+            for index in 0..LEAF_COUNT {
+                let priority = if (index + round) % 7 == 0 { 1e12 } else { 0.1 };
+                tree.update(index, priority);
+            }
+        }
+        for index in 0..LEAF_COUNT {
+            tree.update(index, 0.001 * (index % 10 + 1) as f64);
+        }
+        assert!(!tree.rebuild_if_drifted());
+        // leaves that are set without their ancestors leave the total stale,
+        // like a drift that rounding doesn't explain
+        for index in (0..LEAF_COUNT).step_by(3) {
+            let leaf = tree.first_leaf + index;
+            tree.sum_tree.set_leaf_value(leaf, 1.0 + index as f64 / 7.0);
+        }
+        assert!(tree.drift().abs() > tree.total() * MAX_DRIFT);
+
+        assert!(tree.rebuild_if_drifted());
+        let priority_sum = summed_leaves(&tree);
+        assert!((tree.total() - priority_sum).abs() <= priority_sum * 1e-12);
+        assert!(!tree.rebuild_if_drifted());
     }
 }
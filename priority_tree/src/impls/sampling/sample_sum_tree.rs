@@ -13,7 +13,19 @@ impl<P: Priority> SumTree<P> {
                 (None, Some(right)) => node = right,
                 (Some(left), Some(right)) => {
                     let priority_left = self.value(left);
-                    let chose_left = priority_sum_from_left < priority_left;
+                    // because of rounding errors, the sum can exceed the
+                    // total of the subtree, which would otherwise lead the
+                    // search into an empty subtree at the right edge. Never
+                    // descending into a subtree without priority avoids that
+                    // while still always landing on a leaf with priority
+                    // (unless the whole tree is empty)
+                    let chose_left = if self.value(right) <= P::zero() {
+                        true
+                    } else if priority_left <= P::zero() {
+                        false
+                    } else {
+                        priority_sum_from_left < priority_left
+                    };
                     node = if chose_left {
                         left
                     } else {
@@ -2,8 +2,11 @@ mod impls;
 mod max_tree;
 mod min_tree;
 mod nodes;
+mod priorities;
 mod priority;
+mod proportional_tree;
 mod query_tree;
+mod rank_tree;
 mod sum_tree;
 mod traits;
 mod tree;
@@ -11,17 +14,24 @@ mod tree;
 use max_tree::MaxTree;
 use min_tree::MinTree;
 use nodes::{MaxNode, MinNode};
+pub use priorities::{Priorities, Prioritization};
 pub use priority::Priority;
+pub use proportional_tree::ProportionalTree;
+pub use rank_tree::RankTree;
 use serde::{Deserialize, Serialize};
 use sum_tree::SumTree;
-use traits::{Infinity, NegativeInfinity, Zero};
+use traits::{FromF64, FromRank, Infinity, NegativeInfinity, Powf, Zero};
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct PriorityTree<P: Priority> {
     sum_tree: SumTree<P>,
     min_tree: MinTree<MinNode<P>>,
     max_tree: MaxTree<MaxNode<P>>,
     first_leaf: usize,
+    // a loaded tree starts counting from zero, which at worst delays its
+    // next rebuild
+    #[serde(skip)]
+    updates_since_rebuild: usize,
 }
 
 impl Zero for f64 {
@@ -29,3 +39,21 @@ impl Zero for f64 {
         0.0
     }
 }
+
+impl FromF64 for f64 {
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+}
+
+impl FromRank for f64 {
+    fn from_rank(rank: usize, exponent: f64) -> Self {
+        (rank as f64).powf(-exponent)
+    }
+}
+
+impl Powf for f64 {
+    fn powf(self, exponent: f64) -> Self {
+        f64::powf(self, exponent)
+    }
+}
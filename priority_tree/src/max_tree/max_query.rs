@@ -1,6 +1,6 @@
 use super::{NegativeInfinity, Query, Tree};
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct MaxQuery;
 
 impl<V: Copy + NegativeInfinity + PartialOrd> Query<V> for MaxQuery {
@@ -1,6 +1,6 @@
 use super::{Infinity, Query, Tree};
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct MinQuery;
 
 impl<V: Copy + Infinity + PartialOrd> Query<V> for MinQuery {
@@ -8,7 +8,7 @@ use super::{
 use min_query::MinQuery;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MinTree<V: Copy + Infinity + PartialOrd> {
     tree: QueryTree<V, MinQuery>,
 }
@@ -35,4 +35,20 @@ impl<V: Copy + Infinity + PartialOrd> MinTree<V> {
     pub fn reset_value(&mut self, leaf: usize) {
         self.tree.reset_value(leaf)
     }
+
+    pub fn update_values<I: IntoIterator<Item = (usize, V)>>(&mut self, values: I) {
+        self.tree.update_values(values)
+    }
+
+    pub fn reset_values<I: IntoIterator<Item = usize>>(&mut self, leaves: I) {
+        self.tree.reset_values(leaves)
+    }
+
+    pub fn set_leaf_value(&mut self, leaf: usize, value: V) {
+        self.tree.set_leaf_value(leaf, value)
+    }
+
+    pub fn rebuild(&mut self) {
+        self.tree.rebuild()
+    }
 }
@@ -0,0 +1,175 @@
+use super::{Priority, ProportionalTree, RankTree};
+use rand::distributions::{Distribution, Standard};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Prioritization {
+    // samples in proportion to priority^exponent
+    Proportional,
+    // samples in proportion to rank^-exponent, where rank is the position of
+    // the priority when all priorities are sorted in descending order
+    RankBased,
+}
+
+// Either kind of priority structure, so that the prioritization can be chosen
+// by configuration. In both cases, priority, min, max and total refer to the
+// priorities that sampling follows, while raw_priority and max_raw_priority
+// refer to the priorities as they were passed to update
+#[derive(Clone, Serialize, Deserialize)]
+pub enum Priorities<P: Priority> {
+    Proportional(ProportionalTree<P>),
+    RankBased(RankTree<P>),
+}
+
+impl<P: Priority> Priorities<P> {
+    pub fn with_leaf_count(
+        prioritization: Prioritization,
+        exponent: f64,
+        leaf_count: usize,
+    ) -> Self {
+        match prioritization {
+            Prioritization::Proportional => {
+                Self::Proportional(ProportionalTree::with_leaf_count(leaf_count, exponent))
+            }
+            Prioritization::RankBased => {
+                Self::RankBased(RankTree::with_leaf_count(leaf_count, exponent))
+            }
+        }
+    }
+
+    pub fn with_priorities<I>(
+        prioritization: Prioritization,
+        exponent: f64,
+        leaf_count: usize,
+        priorities: I,
+    ) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Option<P>>,
+    {
+        match prioritization {
+            Prioritization::Proportional => Self::Proportional(ProportionalTree::with_priorities(
+                leaf_count, exponent, priorities,
+            )),
+            Prioritization::RankBased => {
+                Self::RankBased(RankTree::with_priorities(leaf_count, exponent, priorities))
+            }
+        }
+    }
+
+    pub fn prioritization(&self) -> Prioritization {
+        match self {
+            Self::Proportional(_) => Prioritization::Proportional,
+            Self::RankBased(_) => Prioritization::RankBased,
+        }
+    }
+
+    pub fn exponent(&self) -> f64 {
+        match self {
+            Self::Proportional(tree) => tree.exponent(),
+            Self::RankBased(tree) => tree.exponent(),
+        }
+    }
+
+    pub fn set_exponent(&mut self, exponent: f64) {
+        match self {
+            Self::Proportional(tree) => tree.set_exponent(exponent),
+            Self::RankBased(tree) => tree.set_exponent(exponent),
+        }
+    }
+
+    pub fn min(&self) -> Option<P> {
+        match self {
+            Self::Proportional(tree) => tree.min(),
+            Self::RankBased(tree) => tree.min(),
+        }
+    }
+
+    pub fn max(&self) -> Option<P> {
+        match self {
+            Self::Proportional(tree) => tree.max(),
+            Self::RankBased(tree) => tree.max(),
+        }
+    }
+
+    pub fn total(&self) -> P {
+        match self {
+            Self::Proportional(tree) => tree.total(),
+            Self::RankBased(tree) => tree.total(),
+        }
+    }
+
+    pub fn priority(&self, index: usize) -> P {
+        match self {
+            Self::Proportional(tree) => tree.priority(index),
+            Self::RankBased(tree) => tree.priority(index),
+        }
+    }
+
+    pub fn raw_priority(&self, index: usize) -> P {
+        match self {
+            Self::Proportional(tree) => tree.raw_priority(index),
+            Self::RankBased(tree) => tree.raw_priority(index),
+        }
+    }
+
+    pub fn max_raw_priority(&self) -> Option<P> {
+        match self {
+            Self::Proportional(tree) => tree.max_raw_priority(),
+            Self::RankBased(tree) => tree.max_raw_priority(),
+        }
+    }
+
+    pub fn update(&mut self, index: usize, priority: P) {
+        match self {
+            Self::Proportional(tree) => tree.update(index, priority),
+            Self::RankBased(tree) => tree.update(index, priority),
+        }
+    }
+
+    pub fn update_many(&mut self, priorities: &[(usize, P)]) {
+        match self {
+            Self::Proportional(tree) => tree.update_many(priorities),
+            Self::RankBased(tree) => tree.update_many(priorities),
+        }
+    }
+
+    pub fn reset(&mut self, index: usize) {
+        match self {
+            Self::Proportional(tree) => tree.reset(index),
+            Self::RankBased(tree) => tree.reset(index),
+        }
+    }
+
+    pub fn reset_many(&mut self, indices: &[usize]) {
+        match self {
+            Self::Proportional(tree) => tree.reset_many(indices),
+            Self::RankBased(tree) => tree.reset_many(indices),
+        }
+    }
+}
+
+impl<P: Priority> Priorities<P>
+where
+    Standard: Distribution<P>,
+{
+    pub fn sample_from_range<R>(&self, range_start: P, range_end: P, rng: &mut R) -> usize
+    where
+        R: Rng,
+    {
+        match self {
+            Self::Proportional(tree) => tree.sample_from_range(range_start, range_end, rng),
+            Self::RankBased(tree) => tree.sample_from_range(range_start, range_end, rng),
+        }
+    }
+    pub fn sample<R>(&self, rng: &mut R) -> usize
+    where
+        R: Rng,
+    {
+        match self {
+            Self::Proportional(tree) => tree.sample(rng),
+            Self::RankBased(tree) => tree.sample(rng),
+        }
+    }
+}
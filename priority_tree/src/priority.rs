@@ -1,8 +1,11 @@
-use super::Zero;
+use super::{FromF64, FromRank, Powf, Zero};
 use std::ops::{Add, Div, Mul, Sub, SubAssign};
 
 pub trait Priority:
     Zero
+    + FromF64
+    + FromRank
+    + Powf
     + Copy
     + PartialOrd
     + Add<Output = Self>
@@ -14,6 +17,9 @@ pub trait Priority:
 }
 impl<P> Priority for P where
     P: Zero
+        + FromF64
+        + FromRank
+        + Powf
         + Copy
         + PartialOrd
         + Add<Output = Self>
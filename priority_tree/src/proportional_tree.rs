@@ -0,0 +1,149 @@
+// Proportional prioritization, as described in the PER paper. Each index is
+// sampled with a probability proportional to priority^exponent. The
+// priorities are kept as they were passed to update, so that the exponent can
+// be changed at any time, at the cost of rebuilding the tree
+use super::{Priority, PriorityTree};
+use rand::distributions::{Distribution, Standard};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ProportionalTree<P: Priority> {
+    // the priorities as they were passed to update
+    priorities: Vec<Option<P>>,
+    // the priorities raised to the exponent, which sampling follows
+    exponentiated_priorities: PriorityTree<P>,
+    exponent: f64,
+}
+
+impl<P: Priority> ProportionalTree<P> {
+    pub fn with_leaf_count(leaf_count: usize, exponent: f64) -> Self {
+        Self {
+            priorities: vec![None; leaf_count],
+            exponentiated_priorities: PriorityTree::with_leaf_count(leaf_count),
+            exponent,
+        }
+    }
+
+    pub fn with_priorities<I>(leaf_count: usize, exponent: f64, priorities: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Option<P>>,
+    {
+        let mut tree = Self::with_leaf_count(leaf_count, exponent);
+        for (index, priority) in priorities.into_iter().take(leaf_count).enumerate() {
+            tree.priorities[index] = priority.into();
+        }
+        tree.rebuild();
+        tree
+    }
+
+    fn rebuild(&mut self) {
+        let exponentiated_priorities = self
+            .priorities
+            .iter()
+            .map(|priority| priority.map(|priority| priority.powf(self.exponent)));
+        self.exponentiated_priorities =
+            PriorityTree::with_priorities(self.leaf_count(), exponentiated_priorities);
+    }
+
+    pub fn exponent(&self) -> f64 {
+        self.exponent
+    }
+
+    pub fn set_exponent(&mut self, exponent: f64) {
+        if exponent != self.exponent {
+            self.exponent = exponent;
+            self.rebuild();
+        }
+    }
+
+    pub fn leaf_count(&self) -> usize {
+        self.priorities.len()
+    }
+
+    pub fn min(&self) -> Option<P> {
+        self.exponentiated_priorities.min()
+    }
+
+    pub fn max(&self) -> Option<P> {
+        self.exponentiated_priorities.max()
+    }
+
+    pub fn total(&self) -> P {
+        self.exponentiated_priorities.total()
+    }
+
+    // the priority at index raised to the exponent
+    pub fn priority(&self, index: usize) -> P {
+        self.exponentiated_priorities.priority(index)
+    }
+
+    // the priority at index as it was passed to update
+    pub fn raw_priority(&self, index: usize) -> P {
+        self.priorities[index].unwrap_or_else(P::zero)
+    }
+
+    pub fn max_raw_priority(&self) -> Option<P> {
+        if self.exponent > 0.0 {
+            // raising to a positive exponent preserves the order, so the
+            // maximum can be recovered from the tree
+            self.max().map(|max| max.powf(1.0 / self.exponent))
+        } else {
+            self.priorities
+                .iter()
+                .flatten()
+                .copied()
+                .reduce(|max, priority| if max < priority { priority } else { max })
+        }
+    }
+
+    pub fn update(&mut self, index: usize, priority: P) {
+        self.priorities[index] = Some(priority);
+        self.exponentiated_priorities
+            .update(index, priority.powf(self.exponent));
+    }
+
+    pub fn update_many(&mut self, priorities: &[(usize, P)]) {
+        for (index, priority) in priorities {
+            self.priorities[*index] = Some(*priority);
+        }
+        let exponentiated_priorities: Vec<_> = priorities
+            .iter()
+            .map(|(index, priority)| (*index, priority.powf(self.exponent)))
+            .collect();
+        self.exponentiated_priorities
+            .update_many(&exponentiated_priorities);
+    }
+
+    pub fn reset(&mut self, index: usize) {
+        self.priorities[index] = None;
+        self.exponentiated_priorities.reset(index);
+    }
+
+    pub fn reset_many(&mut self, indices: &[usize]) {
+        for index in indices {
+            self.priorities[*index] = None;
+        }
+        self.exponentiated_priorities.reset_many(indices);
+    }
+}
+
+impl<P: Priority> ProportionalTree<P>
+where
+    Standard: Distribution<P>,
+{
+    pub fn sample_from_range<R>(&self, range_start: P, range_end: P, rng: &mut R) -> usize
+    where
+        R: Rng,
+    {
+        self.exponentiated_priorities
+            .sample_from_range(range_start, range_end, rng)
+    }
+    pub fn sample<R>(&self, rng: &mut R) -> usize
+    where
+        R: Rng,
+    {
+        self.exponentiated_priorities.sample(rng)
+    }
+}
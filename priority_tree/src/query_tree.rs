@@ -1,12 +1,13 @@
 use super::tree::Tree;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 
 pub trait Query<V> {
     fn default() -> V;
     fn children_query(tree: &Tree<V>, node: usize) -> V;
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct QueryTree<V, Q: Query<V>> {
     tree: Tree<V>,
     _marker: std::marker::PhantomData<Q>,
@@ -32,6 +33,10 @@ impl<V: Copy, Q: Query<V>> QueryTree<V, Q> {
         self.tree.first_leaf()
     }
 
+    pub fn leaf_count(&self) -> usize {
+        self.tree.leaf_count()
+    }
+
     pub fn value(&self, node: usize) -> V {
         self.tree.value(node)
     }
@@ -47,6 +52,43 @@ impl<V: Copy, Q: Query<V>> QueryTree<V, Q> {
         self.update_value(leaf, Q::default());
     }
 
+    // assumes leaves were provided
+    pub fn update_values<I: IntoIterator<Item = (usize, V)>>(&mut self, values: I) {
+        let mut ancestors = BTreeSet::new();
+        for (leaf, value) in values {
+            self.tree.set_value(leaf, value);
+            ancestors.extend(self.tree.parent(leaf));
+        }
+        // every node has a larger index than its parent, so by visiting the
+        // ancestors in decreasing index order, each one is recomputed once,
+        // after all of its affected children
+        while let Some(node) = ancestors.pop_last() {
+            let value = Q::children_query(&self.tree, node);
+            self.tree.set_value(node, value);
+            ancestors.extend(self.tree.parent(node));
+        }
+    }
+
+    // assumes leaves were provided
+    pub fn reset_values<I: IntoIterator<Item = usize>>(&mut self, leaves: I) {
+        self.update_values(leaves.into_iter().map(|leaf| (leaf, Q::default())));
+    }
+
+    // assumes a leaf was provided. Unlike update_value, the ancestors of the
+    // leaf are left stale until the next rebuild
+    pub fn set_leaf_value(&mut self, leaf: usize, value: V) {
+        self.tree.set_value(leaf, value);
+    }
+
+    // recomputes every internal node from the leaves up, discarding whatever
+    // error the incremental updates have accumulated
+    pub fn rebuild(&mut self) {
+        for node in (0..self.tree.first_leaf()).rev() {
+            let value = Q::children_query(&self.tree, node);
+            self.tree.set_value(node, value);
+        }
+    }
+
     fn update_ancestors(&mut self, mut node: usize) {
         while let Some(parent) = self.tree.parent(node) {
             let value = Q::children_query(&self.tree, parent);
@@ -0,0 +1,148 @@
+use super::Priority;
+use std::cmp::Ordering;
+use std::mem;
+
+// the length of the runs that are sorted directly, before merging them
+const RUN_LEN: usize = 32;
+
+#[derive(Clone)]
+enum Stage<P: Priority> {
+    // copying the priorities, starting from next_index
+    Collecting {
+        values: Vec<P>,
+        next_index: usize,
+    },
+    // sorting runs of RUN_LEN values, starting from the one at next_start
+    SortingRuns {
+        values: Vec<P>,
+        next_start: usize,
+    },
+    // merging pairs of sorted runs of len width from src into dst. left,
+    // right and out are the positions within the pair that is being merged
+    Merging {
+        src: Vec<P>,
+        dst: Vec<P>,
+        width: usize,
+        left: usize,
+        right: usize,
+        out: usize,
+    },
+    Sorted(Vec<P>),
+}
+
+// Sorts priorities in descending order a bounded amount of work at a time, so
+// that the cost of sorting them can be spread over many updates. Updates to
+// the priorities during the sort may or may not be reflected in the result,
+// which is good enough for approximate ranks
+#[derive(Clone)]
+pub struct IncrementalSort<P: Priority> {
+    stage: Stage<P>,
+}
+
+impl<P: Priority> IncrementalSort<P> {
+    pub fn new() -> Self {
+        Self {
+            stage: Stage::Collecting {
+                values: vec![],
+                next_index: 0,
+            },
+        }
+    }
+
+    fn descending(lhs: &P, rhs: &P) -> Ordering {
+        rhs.partial_cmp(lhs).unwrap_or(Ordering::Equal)
+    }
+
+    // the stage that merges runs of len width, unless values is already a
+    // single sorted run. spare is reused as the destination of the merge
+    fn merge_pass(values: Vec<P>, width: usize, spare: Vec<P>) -> Stage<P> {
+        if width >= values.len() {
+            Stage::Sorted(values)
+        } else {
+            Stage::Merging {
+                src: values,
+                dst: spare,
+                width,
+                left: 0,
+                right: width,
+                out: 0,
+            }
+        }
+    }
+
+    // does roughly budget units of work, where a unit is about the cost of
+    // moving a single value. Returns the sorted priorities once done
+    pub fn advance(&mut self, priorities: &[Option<P>], mut budget: usize) -> Option<Vec<P>> {
+        while budget > 0 {
+            match &mut self.stage {
+                Stage::Collecting { values, next_index } => {
+                    let end = priorities.len().min(*next_index + budget);
+                    values.extend(priorities[*next_index..end].iter().flatten());
+                    budget -= end - *next_index;
+                    *next_index = end;
+                    if end == priorities.len() {
+                        let values = mem::take(values);
+                        self.stage = Stage::SortingRuns {
+                            values,
+                            next_start: 0,
+                        };
+                    }
+                }
+                Stage::SortingRuns { values, next_start } => {
+                    let end = values.len().min(*next_start + RUN_LEN);
+                    values[*next_start..end].sort_unstable_by(Self::descending);
+                    budget = budget.saturating_sub(RUN_LEN);
+                    *next_start = end;
+                    if end == values.len() {
+                        let values = mem::take(values);
+                        self.stage = Self::merge_pass(values, RUN_LEN, vec![]);
+                    }
+                }
+                Stage::Merging {
+                    src,
+                    dst,
+                    width,
+                    left,
+                    right,
+                    out,
+                } => {
+                    let len = src.len();
+                    let pair_start = *out - *out % (2 * *width);
+                    let mid = len.min(pair_start + *width);
+                    let end = len.min(pair_start + 2 * *width);
+                    while budget > 0 && *out < end {
+                        // ties go to the left run, which keeps the merge stable
+                        let take_left = *right == end || (*left < mid && src[*right] <= src[*left]);
+                        let value = if take_left {
+                            *left += 1;
+                            src[*left - 1]
+                        } else {
+                            *right += 1;
+                            src[*right - 1]
+                        };
+                        if *out < dst.len() {
+                            dst[*out] = value;
+                        } else {
+                            dst.push(value);
+                        }
+                        *out += 1;
+                        budget -= 1;
+                    }
+                    if *out == len {
+                        let merged = mem::take(dst);
+                        let spare = mem::take(src);
+                        self.stage = Self::merge_pass(merged, *width * 2, spare);
+                    } else if *out == end {
+                        *left = end;
+                        *right = len.min(end + *width);
+                    }
+                }
+                Stage::Sorted(_) => break,
+            }
+        }
+        match &mut self.stage {
+            Stage::Sorted(sorted) => Some(mem::take(sorted)),
+            _ => None,
+        }
+    }
+}
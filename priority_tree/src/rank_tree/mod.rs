@@ -0,0 +1,272 @@
+// Rank-based prioritization, as described in the PER paper. Each index is
+// sampled with a probability proportional to rank^-exponent, where rank is the
+// position of its priority when all priorities are sorted in descending order.
+// Unlike proportional sampling, this is insensitive to outliers in the
+// priorities
+//
+// Keeping every rank exact would mean touching all of them on each update, so
+// the priorities are only re-sorted periodically. In between, the rank of an
+// updated priority is looked up among the priorities as of the last re-sort.
+// The periodic re-sort is spread over the updates that follow it, so that no
+// single update has to wait for all of the priorities to be sorted
+mod incremental_sort;
+
+use super::{Priority, PriorityTree};
+use incremental_sort::IncrementalSort;
+use rand::distributions::{Distribution, Standard};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RankTree<P: Priority> {
+    // the priorities as they were passed to update
+    priorities: Vec<Option<P>>,
+    // the priorities derived from the ranks, which sampling follows
+    rank_priorities: PriorityTree<P>,
+    exponent: f64,
+    // the priorities in descending order, as of the last re-sort
+    sorted_priorities: Vec<P>,
+    max_priority_since_sort: Option<P>,
+    updates_since_sort: usize,
+    // a loaded tree starts over from its last complete sort, which at worst
+    // delays its ranks
+    #[serde(skip, default = "Option::default")]
+    resort: Option<Resort<P>>,
+}
+
+#[derive(Clone)]
+enum Resort<P: Priority> {
+    Sorting {
+        sort: IncrementalSort<P>,
+        // the max of the priorities that were updated since the sort started,
+        // which the sort may have missed
+        max_priority: Option<P>,
+    },
+    // the sorted priorities are in place, and the rank priorities are being
+    // recomputed from them, starting at next_index
+    Ranking {
+        next_index: usize,
+    },
+}
+
+impl<P: Priority> RankTree<P> {
+    pub fn with_leaf_count(leaf_count: usize, exponent: f64) -> Self {
+        Self {
+            priorities: vec![None; leaf_count],
+            rank_priorities: PriorityTree::with_leaf_count(leaf_count),
+            exponent,
+            sorted_priorities: vec![],
+            max_priority_since_sort: None,
+            updates_since_sort: 0,
+            resort: None,
+        }
+    }
+
+    pub fn with_priorities<I>(leaf_count: usize, exponent: f64, priorities: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Option<P>>,
+    {
+        let mut tree = Self::with_leaf_count(leaf_count, exponent);
+        for (index, priority) in priorities.into_iter().take(leaf_count).enumerate() {
+            tree.priorities[index] = priority.into();
+        }
+        tree.sort();
+        tree
+    }
+
+    pub fn exponent(&self) -> f64 {
+        self.exponent
+    }
+
+    pub fn set_exponent(&mut self, exponent: f64) {
+        if exponent != self.exponent {
+            self.exponent = exponent;
+            self.sort();
+        }
+    }
+
+    pub fn leaf_count(&self) -> usize {
+        self.priorities.len()
+    }
+
+    pub fn min(&self) -> Option<P> {
+        self.rank_priorities.min()
+    }
+
+    pub fn max(&self) -> Option<P> {
+        self.rank_priorities.max()
+    }
+
+    pub fn total(&self) -> P {
+        self.rank_priorities.total()
+    }
+
+    // the priority derived from the rank of the priority at index
+    pub fn priority(&self, index: usize) -> P {
+        self.rank_priorities.priority(index)
+    }
+
+    // the priority at index as it was passed to update
+    pub fn raw_priority(&self, index: usize) -> P {
+        self.priorities[index].unwrap_or_else(P::zero)
+    }
+
+    // may overestimate the maximum if it was lowered or reset since the last
+    // re-sort
+    pub fn max_raw_priority(&self) -> Option<P> {
+        let sorted_max = self.sorted_priorities.first().copied();
+        match (sorted_max, self.max_priority_since_sort) {
+            (Some(sorted_max), Some(max_since_sort)) if sorted_max < max_since_sort => {
+                Some(max_since_sort)
+            }
+            (sorted_max, max_since_sort) => sorted_max.or(max_since_sort),
+        }
+    }
+
+    fn rank_priority(&self, priority: P) -> P {
+        // equal priorities share the same rank
+        let rank = self
+            .sorted_priorities
+            .partition_point(|sorted_priority| *sorted_priority > priority)
+            + 1;
+        P::from_rank(rank, self.exponent)
+    }
+
+    fn set_priority(&mut self, index: usize, priority: P) {
+        self.priorities[index] = Some(priority);
+        if self
+            .max_priority_since_sort
+            .is_none_or(|max_priority| max_priority < priority)
+        {
+            self.max_priority_since_sort = Some(priority);
+        }
+        if let Some(Resort::Sorting { max_priority, .. }) = &mut self.resort {
+            if max_priority.is_none_or(|max_priority| max_priority < priority) {
+                *max_priority = Some(priority);
+            }
+        }
+    }
+
+    pub fn update(&mut self, index: usize, priority: P) {
+        self.set_priority(index, priority);
+        let rank_priority = self.rank_priority(priority);
+        self.rank_priorities.update(index, rank_priority);
+        self.count_updates(1);
+    }
+
+    pub fn update_many(&mut self, priorities: &[(usize, P)]) {
+        let rank_priorities: Vec<_> = priorities
+            .iter()
+            .map(|(index, priority)| (*index, self.rank_priority(*priority)))
+            .collect();
+        for (index, priority) in priorities {
+            self.set_priority(*index, *priority);
+        }
+        self.rank_priorities.update_many(&rank_priorities);
+        self.count_updates(priorities.len());
+    }
+
+    pub fn reset(&mut self, index: usize) {
+        self.priorities[index] = None;
+        self.rank_priorities.reset(index);
+        self.count_updates(1);
+    }
+
+    pub fn reset_many(&mut self, indices: &[usize]) {
+        for index in indices {
+            self.priorities[*index] = None;
+        }
+        self.rank_priorities.reset_many(indices);
+        self.count_updates(indices.len());
+    }
+
+    fn count_updates(&mut self, update_count: usize) {
+        // each update does a bounded share of the work of the re-sort
+        const RESORT_WORK_PER_UPDATE: usize = 64;
+        self.updates_since_sort += update_count;
+        // re-sorting once there have been as many updates as there were
+        // priorities keeps the amortized cost per update logarithmic
+        if self.resort.is_none() && self.updates_since_sort >= self.sorted_priorities.len() {
+            self.resort = Some(Resort::Sorting {
+                sort: IncrementalSort::new(),
+                max_priority: None,
+            });
+        }
+        self.advance_resort(update_count * RESORT_WORK_PER_UPDATE);
+    }
+
+    fn advance_resort(&mut self, budget: usize) {
+        match &mut self.resort {
+            None => {}
+            Some(Resort::Sorting { sort, max_priority }) => {
+                if let Some(sorted_priorities) = sort.advance(&self.priorities, budget) {
+                    self.sorted_priorities = sorted_priorities;
+                    self.max_priority_since_sort = *max_priority;
+                    self.updates_since_sort = 0;
+                    self.resort = Some(Resort::Ranking { next_index: 0 });
+                }
+            }
+            Some(Resort::Ranking { next_index }) => {
+                let start = *next_index;
+                let end = self.leaf_count().min(start + budget);
+                let rank_priorities: Vec<_> = (start..end)
+                    .filter_map(|index| {
+                        self.priorities[index].map(|priority| (index, self.rank_priority(priority)))
+                    })
+                    .collect();
+                self.rank_priorities.update_many(&rank_priorities);
+                self.resort = if end == self.leaf_count() {
+                    None
+                } else {
+                    Some(Resort::Ranking { next_index: end })
+                };
+            }
+        }
+    }
+
+    // recomputes the exact rank of every priority at once, dropping any
+    // re-sort in progress
+    pub fn sort(&mut self) {
+        let mut sorted_priorities: Vec<_> = self.priorities.iter().flatten().copied().collect();
+        sorted_priorities
+            .sort_unstable_by(|lhs, rhs| rhs.partial_cmp(lhs).unwrap_or(Ordering::Equal));
+        self.sorted_priorities = sorted_priorities;
+        let rank_priorities: Vec<_> = self
+            .priorities
+            .iter()
+            .map(|priority| priority.map(|priority| self.rank_priority(priority)))
+            .collect();
+        self.rank_priorities = PriorityTree::with_priorities(self.leaf_count(), rank_priorities);
+        self.max_priority_since_sort = None;
+        self.updates_since_sort = 0;
+        self.resort = None;
+    }
+
+    // keeps the priorities at the indices that still fit
+    pub fn resize(&mut self, leaf_count: usize) {
+        let kept_count = leaf_count.min(self.leaf_count());
+        let priorities: Vec<_> = self.priorities[..kept_count].to_vec();
+        *self = Self::with_priorities(leaf_count, self.exponent, priorities);
+    }
+}
+
+impl<P: Priority> RankTree<P>
+where
+    Standard: Distribution<P>,
+{
+    pub fn sample_from_range<R>(&self, range_start: P, range_end: P, rng: &mut R) -> usize
+    where
+        R: Rng,
+    {
+        self.rank_priorities
+            .sample_from_range(range_start, range_end, rng)
+    }
+    pub fn sample<R>(&self, rng: &mut R) -> usize
+    where
+        R: Rng,
+    {
+        self.rank_priorities.sample(rng)
+    }
+}
@@ -8,7 +8,7 @@ use super::{
 use serde::{Deserialize, Serialize};
 use sum_query::SumQuery;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SumTree<V: Copy + Zero + std::ops::Add<Output = V>> {
     tree: QueryTree<V, SumQuery>,
 }
@@ -32,6 +32,10 @@ impl<V: Copy + Zero + std::ops::Add<Output = V>> SumTree<V> {
         self.tree.first_leaf()
     }
 
+    pub fn leaf_count(&self) -> usize {
+        self.tree.leaf_count()
+    }
+
     pub fn value(&self, node: usize) -> V {
         self.tree.value(node)
     }
@@ -43,4 +47,20 @@ impl<V: Copy + Zero + std::ops::Add<Output = V>> SumTree<V> {
     pub fn reset_value(&mut self, leaf: usize) {
         self.tree.reset_value(leaf)
     }
+
+    pub fn update_values<I: IntoIterator<Item = (usize, V)>>(&mut self, values: I) {
+        self.tree.update_values(values)
+    }
+
+    pub fn reset_values<I: IntoIterator<Item = usize>>(&mut self, leaves: I) {
+        self.tree.reset_values(leaves)
+    }
+
+    pub fn set_leaf_value(&mut self, leaf: usize, value: V) {
+        self.tree.set_leaf_value(leaf, value)
+    }
+
+    pub fn rebuild(&mut self) {
+        self.tree.rebuild()
+    }
 }
@@ -1,6 +1,6 @@
 use super::{Query, Tree, Zero};
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct SumQuery;
 
 impl<V: Copy + Zero + std::ops::Add<Output = V>> Query<V> for SumQuery {
@@ -9,3 +9,15 @@ pub trait NegativeInfinity {
 pub trait Infinity {
     fn infinity() -> Self;
 }
+
+pub trait FromRank {
+    fn from_rank(rank: usize, exponent: f64) -> Self;
+}
+
+pub trait Powf {
+    fn powf(self, exponent: f64) -> Self;
+}
+
+pub trait FromF64 {
+    fn from_f64(value: f64) -> Self;
+}
@@ -5,7 +5,7 @@ enum TreeDir {
     Right,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Tree<V> {
     nodes: Vec<V>,
     leaf_count: usize,
@@ -14,7 +14,8 @@ pub struct Tree<V> {
 impl<V: Copy> Tree<V> {
     pub fn new(value: V, leaf_count: usize) -> Self {
         let leaf_count_prev_power_of_two = leaf_count.next_power_of_two() / 2;
-        let tree_len_prev_power_of_two = 2 * leaf_count_prev_power_of_two - 1;
+        // a single leaf is the root itself, with no internal nodes above it
+        let tree_len_prev_power_of_two = (2 * leaf_count_prev_power_of_two).saturating_sub(1);
         Self {
             nodes: vec![value; tree_len_prev_power_of_two + leaf_count],
             leaf_count,
@@ -65,6 +66,10 @@ impl<V: Copy> Tree<V> {
         }
     }
 
+    pub fn leaf_count(&self) -> usize {
+        self.leaf_count
+    }
+
     pub fn value(&self, node: usize) -> V {
         self.nodes[node]
     }
@@ -0,0 +1,51 @@
+use clap::{Parser, ValueEnum};
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+pub struct Args {
+    /// The number of transitions the learners may sample for every transition
+    /// inserted by the actors.
+    ///
+    /// If unset, sampling and insertion are not rate limited
+    #[arg(short, long, required = false)]
+    pub samples_per_insert: Option<f64>,
+    /// How far, in sampled transitions, the observed ratio may stray from
+    /// samples_per_insert before requests are rejected.
+    ///
+    /// Should be well above both the learner's batch size and the actors'
+    /// insertion batch size times samples_per_insert, otherwise neither side
+    /// may be able to make progress
+    #[arg(short, long, default_value_t = 20_000.0)]
+    pub error_buffer: f64,
+    /// The minimal number of transitions the replay must hold before sampling
+    /// is allowed
+    #[arg(short, long, default_value_t = 50_000)]
+    pub min_size_to_sample: usize,
+    /// If set, transitions are sampled by the rank of their priority rather
+    /// than in proportion to it, with probabilities proportional to
+    /// rank^-alpha
+    #[arg(short, long)]
+    pub rank_based: bool,
+    /// The prioritization exponent. Learners may change it at runtime
+    #[arg(short, long, default_value_t = 0.6)]
+    pub alpha: f64,
+    /// How the priority of a newly inserted transition is chosen
+    #[arg(short, long, value_enum, default_value_t = InitialPriorityKind::Supplied)]
+    pub initial_priority: InitialPriorityKind,
+    /// The initial priority of every transition when initial_priority is
+    /// `fixed`
+    #[arg(short, long, default_value_t = 1.0)]
+    pub fixed_initial_priority: f64,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum InitialPriorityKind {
+    /// The priority computed by the actor, or current-max if it sent none
+    Supplied,
+    /// The maximum priority currently in the replay
+    CurrentMax,
+    /// The maximum priority ever stored in the replay
+    AllTimeMax,
+    /// The value of fixed_initial_priority
+    Fixed,
+}
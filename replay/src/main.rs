@@ -1,10 +1,18 @@
+mod args;
+mod rate_limiter;
 mod serializer_hack;
 
+use args::{Args, InitialPriorityKind};
+use clap::Parser;
 use coordinator_client::CoordinatorClient;
 use local_ip_address::local_ip;
-use packets::{ReplayRequest, ReplaySettings, SampleBatchErrorKind, SampleBatchResult};
+use packets::{
+    InsertBatchErrorKind, InsertBatchResult, ReplayRequest, ReplaySettings, SampleBatchErrorKind,
+    SampleBatchResult,
+};
 use prompt::prompt_user_for_service_ip_addr;
-use replay_memories::ReplayRing;
+use rate_limiter::RateLimiter;
+use replay_memories::{InitialPriority, Prioritization, ReplayRing};
 use serializer_hack::{SampleBatchReplySerializer, SampleBatchResultSerializer};
 use std::net::{Ipv4Addr, TcpListener};
 
@@ -41,6 +49,7 @@ use jemallocator::Jemalloc;
 static GLOBAL: Jemalloc = Jemalloc;
 
 fn main() {
+    let args = Args::parse();
     let coordinator_ip_addr = prompt_user_for_service_ip_addr("coordinator");
     println!("coordinator ip addr set to {}...", coordinator_ip_addr);
     let coordinator_addr = (coordinator_ip_addr, ports::COORDINATOR).into();
@@ -50,13 +59,32 @@ fn main() {
     let local_port = socket.local_addr().unwrap().port();
     let local_addr = (local_ip_addr, local_port).into();
     let settings = coordinator_client.replay_conn(local_addr);
-    run(socket, settings);
+    run(socket, args, settings);
 }
 
-fn run(socket: TcpListener, _settings: ReplaySettings) {
+fn run(socket: TcpListener, args: Args, _settings: ReplaySettings) {
     const REPLAY_MAX_LEN: usize = 3_000_000;
     const REPLAY_TRUNCATED_LEN: usize = 2_000_000;
-    let mut replay = ReplayRing::with_max_size(REPLAY_MAX_LEN);
+    let prioritization = if args.rank_based {
+        Prioritization::RankBased
+    } else {
+        Prioritization::Proportional
+    };
+    let mut replay = ReplayRing::with_prioritization(REPLAY_MAX_LEN, prioritization, args.alpha);
+    let initial_priority = match args.initial_priority {
+        InitialPriorityKind::Supplied => InitialPriority::Supplied,
+        InitialPriorityKind::CurrentMax => InitialPriority::CurrentMax,
+        InitialPriorityKind::AllTimeMax => InitialPriority::AllTimeMax,
+        InitialPriorityKind::Fixed => InitialPriority::Fixed(args.fixed_initial_priority),
+    };
+    replay.set_initial_priority_policy(initial_priority);
+    let mut rate_limiter = args.samples_per_insert.map(|samples_per_insert| {
+        RateLimiter::new(
+            samples_per_insert,
+            args.min_size_to_sample,
+            args.error_buffer,
+        )
+    });
     loop {
         let (stream, _source_addr) = socket.accept().unwrap();
         let request = tcp_io::deserialize_from(&stream).unwrap();
@@ -65,11 +93,17 @@ fn run(socket: TcpListener, _settings: ReplaySettings) {
                 replay.truncate(REPLAY_TRUNCATED_LEN);
             }
             ReplayRequest::SampleBatch { batch_len } => {
-                const MIN_SAMPLING_REPLAY_SIZE: usize = 50_000;
-                if replay.len() < MIN_SAMPLING_REPLAY_SIZE {
+                let rate_limited = rate_limiter
+                    .as_ref()
+                    .is_some_and(|rate_limiter| !rate_limiter.can_sample(batch_len));
+                if replay.len() < args.min_size_to_sample {
                     let err = SampleBatchErrorKind::NotEnoughTransitions;
                     let result: SampleBatchResult = Err(err);
                     tcp_io::serialize_into(stream, &result).unwrap();
+                } else if rate_limited {
+                    let err = SampleBatchErrorKind::RateLimited;
+                    let result: SampleBatchResult = Err(err);
+                    tcp_io::serialize_into(stream, &result).unwrap();
                 } else {
                     let batch = replay.sample_batch(batch_len);
                     let reply = SampleBatchReplySerializer {
@@ -79,12 +113,31 @@ fn run(socket: TcpListener, _settings: ReplaySettings) {
                     };
                     let result: SampleBatchResultSerializer = Ok(reply);
                     tcp_io::serialize_into(&stream, &result).unwrap();
+                    if let Some(ref mut rate_limiter) = rate_limiter {
+                        rate_limiter.sample(batch_len);
+                    }
                 }
             }
             ReplayRequest::InsertBatch { batch } => {
-                for insertion in batch {
-                    replay.add_transition_with_priority(insertion.transition, insertion.priority);
-                }
+                let rate_limited = rate_limiter
+                    .as_ref()
+                    .is_some_and(|rate_limiter| !rate_limiter.can_insert(batch.len()));
+                let result: InsertBatchResult = if rate_limited {
+                    Err(InsertBatchErrorKind::RateLimited)
+                } else {
+                    if let Some(ref mut rate_limiter) = rate_limiter {
+                        rate_limiter.insert(batch.len());
+                    }
+                    for insertion in batch {
+                        replay.add_transition(
+                            insertion.transition,
+                            insertion.priority,
+                            insertion.metadata,
+                        );
+                    }
+                    Ok(())
+                };
+                tcp_io::serialize_into(stream, &result).unwrap();
             }
             ReplayRequest::UpdateBatchPriorities { batch } => {
                 let indices = batch
@@ -97,6 +150,25 @@ fn run(socket: TcpListener, _settings: ReplaySettings) {
                     .collect::<Vec<_>>();
                 replay.update_priorities(&indices, &priorities);
             }
+            ReplayRequest::SetAlpha { alpha } => {
+                replay.set_alpha(alpha);
+            }
+            ReplayRequest::Stats => {
+                tcp_io::serialize_into(stream, &replay.stats()).unwrap();
+            }
+            ReplayRequest::Len => {
+                tcp_io::serialize_into(stream, &replay.len()).unwrap();
+            }
+            ReplayRequest::FetchTransitions { filter } => {
+                // serializes the same way as a FetchTransitionsReply, without
+                // cloning the transitions
+                let transitions = replay.fetch(filter);
+                tcp_io::serialize_into(stream, &transitions).unwrap();
+            }
+            ReplayRequest::DeleteTransitions { filter } => {
+                let deleted_count = replay.delete(filter);
+                tcp_io::serialize_into(stream, &deleted_count).unwrap();
+            }
         }
     }
 }
@@ -0,0 +1,185 @@
+// Keeps the ratio between sampled and inserted transitions close to a fixed
+// target, in the spirit of Reverb's SampleToInsertRatio limiter.
+// The limiter tracks the difference
+//     inserted * samples_per_insert - sampled
+// which should stay around samples_per_insert * min_size_to_sample, i. e. the
+// difference right after the replay has first become large enough to sample
+// from. An insertion is refused when it would push the difference above the
+// tolerance band, and a sample is refused when it would push it below the band.
+// Insertions made before min_size_to_sample transitions have been inserted are
+// always accepted, since otherwise sampling could never start
+pub struct RateLimiter {
+    samples_per_insert: f64,
+    min_size_to_sample: usize,
+    min_diff: f64,
+    max_diff: f64,
+    inserted: u64,
+    sampled: u64,
+}
+
+impl RateLimiter {
+    pub fn new(samples_per_insert: f64, min_size_to_sample: usize, error_buffer: f64) -> Self {
+        let offset = samples_per_insert * min_size_to_sample as f64;
+        Self {
+            samples_per_insert,
+            min_size_to_sample,
+            min_diff: offset - error_buffer,
+            max_diff: offset + error_buffer,
+            inserted: 0,
+            sampled: 0,
+        }
+    }
+
+    fn diff(&self, inserted: u64, sampled: u64) -> f64 {
+        inserted as f64 * self.samples_per_insert - sampled as f64
+    }
+
+    pub fn can_insert(&self, count: usize) -> bool {
+        let inserted = self.inserted + count as u64;
+        inserted <= self.min_size_to_sample as u64
+            || self.diff(inserted, self.sampled) <= self.max_diff
+    }
+
+    pub fn can_sample(&self, count: usize) -> bool {
+        let sampled = self.sampled + count as u64;
+        self.diff(self.inserted, sampled) >= self.min_diff
+    }
+
+    pub fn insert(&mut self, count: usize) {
+        self.inserted += count as u64;
+    }
+
+    pub fn sample(&mut self, count: usize) {
+        self.sampled += count as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // With 2 samples per insert and a minimal size of 10 the difference is
+    // expected to stay around 20, and an error buffer of 4 gives the band [16, 24]
+    fn limiter() -> RateLimiter {
+        RateLimiter::new(2.0, 10, 4.0)
+    }
+
+    #[test]
+    fn first_insert_is_accepted_before_any_sample() {
+        let rate_limiter = limiter();
+        assert!(rate_limiter.can_insert(1), "the first insert was refused");
+        assert!(
+            rate_limiter.can_insert(10),
+            "a first batch of min_size_to_sample was refused"
+        );
+        assert!(
+            !rate_limiter.can_sample(1),
+            "sampling was allowed from an empty replay"
+        );
+    }
+
+    #[test]
+    fn sampling_opens_at_the_lower_edge_near_min_size_to_sample() {
+        let mut rate_limiter = limiter();
+        rate_limiter.insert(8);
+        assert!(
+            !rate_limiter.can_sample(1),
+            "sampling was allowed below the band"
+        );
+        rate_limiter.insert(1);
+        assert!(
+            rate_limiter.can_sample(2),
+            "a sample landing on the lower edge was refused"
+        );
+        assert!(
+            !rate_limiter.can_sample(3),
+            "a sample landing below the lower edge was allowed"
+        );
+    }
+
+    #[test]
+    fn insert_is_refused_past_the_upper_edge() {
+        let mut rate_limiter = limiter();
+        rate_limiter.insert(10);
+        assert!(
+            rate_limiter.can_insert(2),
+            "an insert landing on the upper edge was refused"
+        );
+        assert!(
+            !rate_limiter.can_insert(3),
+            "an insert landing above the upper edge was allowed"
+        );
+    }
+
+    #[test]
+    fn sample_is_refused_past_the_lower_edge() {
+        let mut rate_limiter = limiter();
+        rate_limiter.insert(10);
+        assert!(
+            rate_limiter.can_sample(4),
+            "a sample landing on the lower edge was refused"
+        );
+        assert!(
+            !rate_limiter.can_sample(5),
+            "a sample landing below the lower edge was allowed"
+        );
+    }
+
+    #[test]
+    fn batch_straddling_the_band_is_refused_as_a_whole() {
+        let mut rate_limiter = limiter();
+        rate_limiter.insert(10);
+        rate_limiter.sample(3);
+        // The difference is 17, inside the band
+        assert!(rate_limiter.can_insert(3), "a fitting insert was refused");
+        assert!(
+            !rate_limiter.can_insert(4),
+            "an insert batch crossing the upper edge was allowed"
+        );
+        assert!(rate_limiter.can_sample(1), "a fitting sample was refused");
+        assert!(
+            !rate_limiter.can_sample(2),
+            "a sample batch crossing the lower edge was allowed"
+        );
+    }
+
+    #[test]
+    fn batch_crossing_min_size_to_sample_is_judged_by_the_band() {
+        let mut rate_limiter = limiter();
+        rate_limiter.insert(8);
+        assert!(
+            rate_limiter.can_insert(4),
+            "a batch crossing min_size_to_sample within the band was refused"
+        );
+        assert!(
+            !rate_limiter.can_insert(5),
+            "a batch crossing min_size_to_sample above the band was allowed"
+        );
+    }
+
+    #[test]
+    fn blocking_is_lifted_by_the_other_side() {
+        let mut rate_limiter = limiter();
+        rate_limiter.insert(12);
+        assert!(
+            !rate_limiter.can_insert(1),
+            "inserts were not blocked at the upper edge"
+        );
+        rate_limiter.sample(2);
+        assert!(
+            rate_limiter.can_insert(1),
+            "sampling did not unblock inserts"
+        );
+
+        rate_limiter.sample(6);
+        assert!(
+            !rate_limiter.can_sample(1),
+            "samples were not blocked at the lower edge"
+        );
+        rate_limiter.insert(1);
+        assert!(
+            rate_limiter.can_sample(1),
+            "inserting did not unblock samples"
+        );
+    }
+}
@@ -1,9 +1,11 @@
 mod compressed_image;
+mod metadata;
 mod state;
 mod transition;
 
 pub use compressed_image::CompressedImageOwned2;
-pub use state::{CompressedRcState, CompressedState, GenericState, SavedState, State};
+pub use metadata::{TransitionFilter, TransitionMetadata};
+pub use state::{CompressedArcState, CompressedState, GenericState, SavedState, State};
 pub use transition::{
-    CompressedRcTransition, CompressedTransition, GenericTransition, SavedTransition, Transition,
+    CompressedArcTransition, CompressedTransition, GenericTransition, SavedTransition, Transition,
 };
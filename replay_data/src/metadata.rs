@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Where and when a transition was produced
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct TransitionMetadata {
+    pub actor_id: usize,
+    // only unique among the episodes of the same actor
+    pub episode_id: u64,
+    // the index of the transition within its episode
+    pub step: u32,
+    // milliseconds since the unix epoch
+    pub timestamp: u64,
+}
+
+impl TransitionMetadata {
+    // timestamped with the current time
+    pub fn new(actor_id: usize, episode_id: u64, step: u32) -> Self {
+        Self {
+            actor_id,
+            episode_id,
+            step,
+            timestamp: Self::current_timestamp(),
+        }
+    }
+    pub fn current_timestamp() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64
+    }
+}
+
+// Selects transitions by their metadata
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum TransitionFilter {
+    Actor { actor_id: usize },
+    Episode { actor_id: usize, episode_id: u64 },
+}
+
+impl TransitionFilter {
+    pub fn matches(&self, metadata: &TransitionMetadata) -> bool {
+        match *self {
+            Self::Actor { actor_id } => metadata.actor_id == actor_id,
+            Self::Episode {
+                actor_id,
+                episode_id,
+            } => metadata.actor_id == actor_id && metadata.episode_id == episode_id,
+        }
+    }
+}
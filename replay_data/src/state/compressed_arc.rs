@@ -0,0 +1,5 @@
+use super::GenericState;
+use crate::compressed_image::CompressedImageOwned2;
+use std::sync::Arc;
+
+pub type CompressedArcState = GenericState<Arc<CompressedImageOwned2>>;
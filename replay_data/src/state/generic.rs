@@ -1,16 +1,24 @@
 use serde::{Deserialize, Serialize};
 
+// A stack of consecutive frames. The number of frames isn't fixed, so that
+// it can follow the state shape of the model
 #[derive(Clone, Serialize, Deserialize)]
-pub struct GenericState<F>([F; 4]);
+pub struct GenericState<F>(Vec<F>);
 
 impl<F> GenericState<F> {
-    pub fn frames(&self) -> &[F; 4] {
+    pub fn frames(&self) -> &[F] {
         &self.0
     }
 }
 
-impl<F> From<[F; 4]> for GenericState<F> {
-    fn from(frames: [F; 4]) -> Self {
+impl<F> From<Vec<F>> for GenericState<F> {
+    fn from(frames: Vec<F>) -> Self {
         Self(frames)
     }
 }
+
+impl<F, const N: usize> From<[F; N]> for GenericState<F> {
+    fn from(frames: [F; N]) -> Self {
+        Self(Vec::from(frames))
+    }
+}
@@ -1,23 +1,29 @@
 mod compressed;
-mod compressed_rc;
+mod compressed_arc;
 mod generic;
 mod normal;
 mod saved;
 
 pub use compressed::CompressedState;
-pub use compressed_rc::CompressedRcState;
+pub use compressed_arc::CompressedArcState;
 pub use generic::GenericState;
 pub use normal::State;
 pub use saved::SavedState;
 
 impl From<&CompressedState> for State {
     fn from(state: &CompressedState) -> Self {
-        Self::from(state.frames().each_ref().map(|image| image.into()))
+        let frames: Vec<_> = state.frames().iter().map(|image| image.into()).collect();
+        Self::from(frames)
     }
 }
 
-impl From<&CompressedRcState> for State {
-    fn from(state: &CompressedRcState) -> Self {
-        Self::from(state.frames().each_ref().map(|image| image.as_ref().into()))
+impl From<&CompressedArcState> for State {
+    fn from(state: &CompressedArcState) -> Self {
+        let frames: Vec<_> = state
+            .frames()
+            .iter()
+            .map(|image| image.as_ref().into())
+            .collect();
+        Self::from(frames)
     }
 }
@@ -1,7 +1,7 @@
-use crate::state::{CompressedRcState, CompressedState, SavedState, State};
+use crate::state::{CompressedArcState, CompressedState, SavedState, State};
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct GenericTransition<S> {
     pub state: S,
     pub next_state: S,
@@ -12,5 +12,5 @@ pub struct GenericTransition<S> {
 
 pub type Transition = GenericTransition<State>;
 pub type CompressedTransition = GenericTransition<CompressedState>;
-pub type CompressedRcTransition = GenericTransition<CompressedRcState>;
+pub type CompressedArcTransition = GenericTransition<CompressedArcState>;
 pub type SavedTransition = GenericTransition<SavedState>;
@@ -0,0 +1,15 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+pub struct Args {
+    /// Path of the saved replay memory to export (the memory directory of a
+    /// save).
+    pub replay_path: PathBuf,
+    /// Path to which the dataset should be written.
+    pub output_path: PathBuf,
+    /// The maximal number of transitions in each shard of the dataset.
+    #[arg(short, long, default_value_t = 10_000)]
+    pub shard_len: usize,
+}
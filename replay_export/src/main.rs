@@ -0,0 +1,24 @@
+mod args;
+
+use args::Args;
+use clap::Parser;
+use replay_memories::ReplayPrioritized;
+
+fn main() {
+    let args = Args::parse();
+    println!("loading replay from {}...", args.replay_path.display());
+    let replay = match ReplayPrioritized::from_saved(&args.replay_path) {
+        Ok(replay) => replay,
+        Err(e) => {
+            eprintln!("could not load {}: {}", args.replay_path.display(), e);
+            std::process::exit(1);
+        }
+    };
+    println!(
+        "exporting {} transitions to {}...",
+        replay.len(),
+        args.output_path.display()
+    );
+    replay.export_dataset(&args.output_path, args.shard_len);
+    println!("done");
+}
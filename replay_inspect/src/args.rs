@@ -0,0 +1,19 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+pub struct Args {
+    /// Path of the saved replay memory to inspect (the memory directory of a
+    /// save).
+    pub replay_path: PathBuf,
+    /// Indices of transitions to render as png strips.
+    ///
+    /// Each strip shows the frames of the state (left) and the next state
+    /// (right), from oldest (top) to newest (bottom)
+    #[arg(short, long, value_delimiter = ',')]
+    pub render: Vec<usize>,
+    /// Path to which rendered strips are written.
+    #[arg(short, long, default_value = "inspect")]
+    pub output_path: PathBuf,
+}
@@ -0,0 +1,43 @@
+mod args;
+mod render;
+mod stats;
+
+use args::Args;
+use clap::Parser;
+use render::render_transition;
+use replay_memories::ReplayPrioritized;
+use stats::ReplayStats;
+use std::collections::BTreeSet;
+use std::fs;
+
+fn main() {
+    let args = Args::parse();
+    println!("loading replay from {}...", args.replay_path.display());
+    let replay = match ReplayPrioritized::from_saved(&args.replay_path) {
+        Ok(replay) => replay,
+        Err(e) => {
+            eprintln!("could not load {}: {}", args.replay_path.display(), e);
+            std::process::exit(1);
+        }
+    };
+    let mut stats = ReplayStats::new(replay.max_size());
+    for (_index, priority, transition) in replay.iter() {
+        stats.add(priority, transition);
+    }
+    stats.print();
+    if !args.render.is_empty() {
+        fs::create_dir_all(&args.output_path).unwrap();
+    }
+    // one pass over the replay renders every requested transition
+    let mut pending_indices: BTreeSet<usize> = args.render.into_iter().collect();
+    for (index, _priority, transition) in replay.iter() {
+        if pending_indices.remove(&index) {
+            let path = args.output_path.join(format!("transition_{}.png", index));
+            render_transition(&path, transition);
+            println!("rendered transition {} to {}", index, path.display());
+        }
+    }
+    for index in pending_indices {
+        eprintln!("no transition at index {}", index);
+    }
+}
@@ -0,0 +1,32 @@
+use file_io::create_file_buf_write;
+use image::{ImageOwned2, ImageRef};
+use replay_data::{CompressedArcTransition, State};
+use std::path::Path;
+
+// Writes a png with the frames of the state on the left and the frames of the
+// next state on the right. Frames only have red and green channels, so the
+// blue channel is left empty
+pub fn render_transition<P: AsRef<Path>>(path: P, transition: &CompressedArcTransition) {
+    let state = State::from(&transition.state).concat_frames();
+    let next_state = State::from(&transition.next_state).concat_frames();
+    let (width, height) = (state.width(), state.height());
+    let strip_width = 2 * width;
+    let mut data = Vec::with_capacity(3 * (strip_width * height) as usize);
+    for y in 0..height {
+        push_row(&mut data, &state, y);
+        push_row(&mut data, &next_state, y);
+    }
+    let file = create_file_buf_write(path).unwrap();
+    let mut encoder = png::Encoder::new(file, strip_width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().unwrap();
+    writer.write_image_data(&data).unwrap();
+}
+
+fn push_row(data: &mut Vec<u8>, image: &ImageOwned2, y: u32) {
+    for x in 0..image.width() {
+        let color = image.get_pixel_color(x, y);
+        data.extend_from_slice(&[color.0, color.1, 0]);
+    }
+}
@@ -0,0 +1,102 @@
+use replay_data::{CompressedArcState, CompressedArcTransition, CompressedImageOwned2};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+
+const PRIORITY_QUANTILES: [f64; 7] = [0.0, 0.25, 0.5, 0.75, 0.9, 0.99, 1.0];
+
+pub struct ReplayStats {
+    len: usize,
+    max_size: usize,
+    action_counts: BTreeMap<u8, usize>,
+    reward_counts: HashMap<u64, usize>,
+    terminated_count: usize,
+    priorities: Vec<f64>,
+    frame_ref_count: usize,
+    frame_pointers: HashSet<*const CompressedImageOwned2>,
+}
+
+impl ReplayStats {
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            len: 0,
+            max_size,
+            action_counts: BTreeMap::new(),
+            reward_counts: HashMap::new(),
+            terminated_count: 0,
+            priorities: vec![],
+            frame_ref_count: 0,
+            frame_pointers: HashSet::new(),
+        }
+    }
+
+    pub fn add(&mut self, priority: f64, transition: &CompressedArcTransition) {
+        self.len += 1;
+        *self.action_counts.entry(transition.action).or_insert(0) += 1;
+        // f64 is neither Eq nor Hash, so rewards are keyed by their bits
+        *self
+            .reward_counts
+            .entry(transition.reward.to_bits())
+            .or_insert(0) += 1;
+        if transition.terminated {
+            self.terminated_count += 1;
+        }
+        self.priorities.push(priority);
+        self.add_state(&transition.state);
+        self.add_state(&transition.next_state);
+    }
+
+    fn add_state(&mut self, state: &CompressedArcState) {
+        for frame in state.frames() {
+            self.frame_ref_count += 1;
+            self.frame_pointers.insert(Arc::as_ptr(frame));
+        }
+    }
+
+    fn fraction(&self, count: usize) -> f64 {
+        count as f64 / self.len as f64
+    }
+
+    fn quantile(sorted: &[f64], q: f64) -> f64 {
+        let index = (q * (sorted.len() - 1) as f64).round() as usize;
+        sorted[index]
+    }
+
+    pub fn print(&self) {
+        println!("transitions: {} / {}", self.len, self.max_size);
+        if self.len == 0 {
+            return;
+        }
+        println!("actions:");
+        for (action, count) in &self.action_counts {
+            println!("    {}: {} ({:.4})", action, count, self.fraction(*count));
+        }
+        let mut reward_counts = self
+            .reward_counts
+            .iter()
+            .map(|(reward, count)| (f64::from_bits(*reward), *count))
+            .collect::<Vec<_>>();
+        reward_counts.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        println!("rewards:");
+        for (reward, count) in reward_counts {
+            println!("    {}: {} ({:.4})", reward, count, self.fraction(count));
+        }
+        println!(
+            "terminated: {} ({:.4})",
+            self.terminated_count,
+            self.fraction(self.terminated_count)
+        );
+        let mut priorities = self.priorities.clone();
+        priorities.sort_by(f64::total_cmp);
+        println!("priority quantiles:");
+        for q in PRIORITY_QUANTILES {
+            println!("    {:.2}: {}", q, Self::quantile(&priorities, q));
+        }
+        let unique_frame_count = self.frame_pointers.len();
+        println!(
+            "frames: {} unique, {} referenced (dedup ratio {:.2})",
+            unique_frame_count,
+            self.frame_ref_count,
+            self.frame_ref_count as f64 / unique_frame_count as f64
+        );
+    }
+}
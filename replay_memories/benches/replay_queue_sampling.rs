@@ -0,0 +1,55 @@
+// Measures the per-batch cost of sampling from a full ReplayQueue at
+// increasing capacities. Run with `cargo bench -p replay_memories`
+use image::{ImageOwned, ImageOwned2};
+use replay_data::{CompressedArcTransition, CompressedImageOwned2};
+use replay_memories::{ReplayMemory, ReplayQueue};
+use std::hint::black_box;
+use std::sync::Arc;
+use std::time::Instant;
+
+const CAPACITIES: [usize; 4] = [1_000, 10_000, 100_000, 1_000_000];
+const BATCH_SIZE: usize = 32;
+const BATCH_COUNT: u32 = 10_000;
+
+fn full_queue(capacity: usize) -> ReplayQueue {
+    // every transition shares the same frame, so that filling large queues
+    // stays cheap
+    let frame: Arc<CompressedImageOwned2> = Arc::new((&ImageOwned2::zeroed(84, 84)).into());
+    let state = [(); 4].map(|_| Arc::clone(&frame));
+    let mut queue = ReplayQueue::with_max_size(capacity);
+    for _ in 0..capacity {
+        queue.add_transition(CompressedArcTransition {
+            state: state.clone().into(),
+            next_state: state.clone().into(),
+            action: 0,
+            reward: 0.0,
+            terminated: false,
+        });
+    }
+    queue
+}
+
+fn bench<F, R>(name: &str, capacity: usize, mut sample_batch: F)
+where
+    F: FnMut() -> R,
+{
+    let start = Instant::now();
+    for _ in 0..BATCH_COUNT {
+        black_box(sample_batch());
+    }
+    let per_batch = start.elapsed() / BATCH_COUNT;
+    println!(
+        "{:<28} capacity {:>9}: {:>10?} per batch",
+        name, capacity, per_batch
+    );
+}
+
+fn main() {
+    for capacity in CAPACITIES {
+        let queue = full_queue(capacity);
+        bench("without replacement", capacity, || queue.sample(BATCH_SIZE));
+        bench("with replacement", capacity, || {
+            queue.sample_batch_with_replacement(BATCH_SIZE)
+        });
+    }
+}
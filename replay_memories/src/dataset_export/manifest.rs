@@ -0,0 +1,63 @@
+use file_io::create_file_buf_write;
+use serde::Serialize;
+use std::path::Path;
+
+const FORMAT_NAME: &str = "vi-ape-x-replay-dataset";
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+pub struct FieldManifest {
+    name: String,
+    file: String,
+    dtype: String,
+    // the shape of the field for a single transition, i. e. without the
+    // leading transition dimension
+    shape: Vec<usize>,
+}
+
+impl FieldManifest {
+    pub fn new(name: &str, dtype: &str, shape: Vec<usize>) -> Self {
+        Self {
+            name: name.to_string(),
+            file: format!("{}.npy", name),
+            dtype: dtype.to_string(),
+            shape,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ShardManifest {
+    pub name: String,
+    pub transition_count: usize,
+}
+
+#[derive(Serialize)]
+pub struct Manifest {
+    format: String,
+    format_version: u32,
+    transition_count: usize,
+    fields: Vec<FieldManifest>,
+    shards: Vec<ShardManifest>,
+}
+
+impl Manifest {
+    pub fn new(
+        transition_count: usize,
+        fields: Vec<FieldManifest>,
+        shards: Vec<ShardManifest>,
+    ) -> Self {
+        Self {
+            format: FORMAT_NAME.to_string(),
+            format_version: FORMAT_VERSION,
+            transition_count,
+            fields,
+            shards,
+        }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) {
+        let file = create_file_buf_write(path).unwrap();
+        serde_json::to_writer_pretty(file, self).unwrap();
+    }
+}
@@ -0,0 +1,185 @@
+// Exports replay contents to a format that can be read without this codebase,
+// e. g. with numpy or any other .npy reader.
+//
+// The exported directory contains a manifest.json file, describing the dataset,
+// and a number of shard directories (shard_00000, shard_00001, ...). Each shard
+// holds the following arrays, all sharing the same leading dimension n (the
+// number of transitions in that shard):
+//     states.npy       uint8    [n, stack_len, height, width, channels]
+//     next_states.npy  uint8    [n, stack_len, height, width, channels]
+//     actions.npy      uint8    [n]
+//     rewards.npy      float64  [n]
+//     terminated.npy   bool     [n]
+//     priorities.npy   float64  [n]
+// The frames are stored decompressed. Each frame stack is ordered from oldest
+// to newest, and the two channels of each pixel are the red and green channels
+// of the original game frame. Transitions are ordered from oldest to newest
+// across the shards
+
+mod manifest;
+mod npy_writer;
+
+use image::{ImageOwned, ImageOwned2, ImageRef};
+use manifest::{FieldManifest, Manifest, ShardManifest};
+use npy_writer::NpyWriter;
+use replay_data::{CompressedImageOwned2, GenericState, GenericTransition};
+use std::borrow::Borrow;
+use std::fs;
+use std::path::Path;
+
+const CHANNELS: usize = 2;
+
+// the dimensions that every exported state must share
+#[derive(Clone, Copy)]
+struct StateDims {
+    stack_len: usize,
+    width: u32,
+    height: u32,
+}
+
+impl StateDims {
+    fn shape(&self) -> Vec<usize> {
+        vec![
+            self.stack_len,
+            self.height as usize,
+            self.width as usize,
+            CHANNELS,
+        ]
+    }
+}
+
+struct ShardWriter {
+    states: NpyWriter,
+    next_states: NpyWriter,
+    actions: NpyWriter,
+    rewards: NpyWriter,
+    terminated: NpyWriter,
+    priorities: NpyWriter,
+    state_dims: StateDims,
+}
+
+impl ShardWriter {
+    fn create(path: &Path, len: usize, state_dims: StateDims) -> Self {
+        let state_shape = [vec![len], state_dims.shape()].concat();
+        Self {
+            states: NpyWriter::create(path.join("states.npy"), "|u1", &state_shape).unwrap(),
+            next_states: NpyWriter::create(path.join("next_states.npy"), "|u1", &state_shape)
+                .unwrap(),
+            actions: NpyWriter::create(path.join("actions.npy"), "|u1", &[len]).unwrap(),
+            rewards: NpyWriter::create(path.join("rewards.npy"), "<f8", &[len]).unwrap(),
+            terminated: NpyWriter::create(path.join("terminated.npy"), "|b1", &[len]).unwrap(),
+            priorities: NpyWriter::create(path.join("priorities.npy"), "<f8", &[len]).unwrap(),
+            state_dims,
+        }
+    }
+
+    fn write_state<F>(writer: &mut NpyWriter, state: &GenericState<F>, state_dims: StateDims)
+    where
+        F: Borrow<CompressedImageOwned2>,
+    {
+        assert_eq!(
+            state.frames().len(),
+            state_dims.stack_len,
+            "all exported states must have the same number of frames"
+        );
+        for frame in state.frames() {
+            let frame = ImageOwned2::from(frame.borrow());
+            assert_eq!(
+                (frame.width(), frame.height()),
+                (state_dims.width, state_dims.height),
+                "all exported frames must have the same dimensions"
+            );
+            writer.write_bytes(frame.as_ref().data()).unwrap();
+        }
+    }
+
+    fn write<F>(&mut self, priority: f64, transition: &GenericTransition<GenericState<F>>)
+    where
+        F: Borrow<CompressedImageOwned2>,
+    {
+        Self::write_state(&mut self.states, &transition.state, self.state_dims);
+        Self::write_state(
+            &mut self.next_states,
+            &transition.next_state,
+            self.state_dims,
+        );
+        self.actions.write_u8(transition.action).unwrap();
+        self.rewards.write_f64(transition.reward).unwrap();
+        self.terminated.write_bool(transition.terminated).unwrap();
+        self.priorities.write_f64(priority).unwrap();
+    }
+
+    fn finish(self) {
+        self.states.finish().unwrap();
+        self.next_states.finish().unwrap();
+        self.actions.finish().unwrap();
+        self.rewards.finish().unwrap();
+        self.terminated.finish().unwrap();
+        self.priorities.finish().unwrap();
+    }
+}
+
+fn state_dims<F>(transition: &GenericTransition<GenericState<F>>) -> StateDims
+where
+    F: Borrow<CompressedImageOwned2>,
+{
+    let frames = transition.state.frames();
+    let frame = ImageOwned2::from(frames[0].borrow());
+    StateDims {
+        stack_len: frames.len(),
+        width: frame.width(),
+        height: frame.height(),
+    }
+}
+
+fn fields(state_dims: Option<StateDims>) -> Vec<FieldManifest> {
+    let state_shape = state_dims.map_or(vec![], |state_dims| state_dims.shape());
+    vec![
+        FieldManifest::new("states", "uint8", state_shape.clone()),
+        FieldManifest::new("next_states", "uint8", state_shape),
+        FieldManifest::new("actions", "uint8", vec![]),
+        FieldManifest::new("rewards", "float64", vec![]),
+        FieldManifest::new("terminated", "bool", vec![]),
+        FieldManifest::new("priorities", "float64", vec![]),
+    ]
+}
+
+// Writes the given (priority, transition) pairs to path, splitting them into
+// shards of at most shard_len transitions each
+pub fn export_dataset<'a, P, I, F>(path: P, transitions: I, shard_len: usize)
+where
+    P: AsRef<Path>,
+    I: ExactSizeIterator<Item = (f64, &'a GenericTransition<GenericState<F>>)>,
+    F: Borrow<CompressedImageOwned2> + 'a,
+{
+    assert!(shard_len > 0, "shard_len must be positive");
+    let path = path.as_ref();
+    fs::create_dir_all(path).unwrap();
+    let transition_count = transitions.len();
+    let mut transitions = transitions.peekable();
+    let state_dims = transitions
+        .peek()
+        .map(|(_priority, transition)| state_dims(transition));
+    let mut shards = vec![];
+    let mut remaining = transition_count;
+    while remaining > 0 {
+        let shard_transition_count = remaining.min(shard_len);
+        let shard_name = format!("shard_{:05}", shards.len());
+        let shard_path = path.join(&shard_name);
+        fs::create_dir_all(&shard_path).unwrap();
+        // state_dims is set whenever there are transitions left to export
+        let mut writer =
+            ShardWriter::create(&shard_path, shard_transition_count, state_dims.unwrap());
+        for (priority, transition) in transitions.by_ref().take(shard_transition_count) {
+            writer.write(priority, transition);
+        }
+        writer.finish();
+        shards.push(ShardManifest {
+            name: shard_name,
+            transition_count: shard_transition_count,
+        });
+        remaining -= shard_transition_count;
+    }
+    let manifest = Manifest::new(transition_count, fields(state_dims), shards);
+    manifest.save(path.join("manifest.json"));
+}
@@ -0,0 +1,69 @@
+use file_io::create_file_buf_write;
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+// Streams the contents of a single array into a .npy file (format version 1.0,
+// see [https://numpy.org/doc/stable/reference/generated/numpy.lib.format.html]).
+// The header has to state the full shape of the array, so the shape must be
+// known upfront, and the caller is responsible for writing exactly as many
+// elements as it declares
+pub struct NpyWriter {
+    file: BufWriter<File>,
+}
+
+impl NpyWriter {
+    pub fn create<P: AsRef<Path>>(path: P, descr: &str, shape: &[usize]) -> io::Result<Self> {
+        let mut file = create_file_buf_write(path)?;
+        file.write_all(&Self::header(descr, shape))?;
+        Ok(Self { file })
+    }
+
+    fn header(descr: &str, shape: &[usize]) -> Vec<u8> {
+        const MAGIC: &[u8] = b"\x93NUMPY\x01\x00";
+        const ALIGNMENT: usize = 64;
+        let shape = match shape {
+            [len] => format!("({},)", len),
+            shape => {
+                let dims = shape.iter().map(usize::to_string).collect::<Vec<_>>();
+                format!("({})", dims.join(", "))
+            }
+        };
+        let mut dict = format!(
+            "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+            descr, shape
+        );
+        // the header (magic, length field, dict and the terminating newline)
+        // is padded with spaces so that the array data starts aligned
+        let unpadded_len = MAGIC.len() + 2 + dict.len() + 1;
+        let padding = (ALIGNMENT - unpadded_len % ALIGNMENT) % ALIGNMENT;
+        dict.push_str(&" ".repeat(padding));
+        dict.push('\n');
+        let dict_len = u16::try_from(dict.len()).expect("npy header is too long");
+        let mut header = MAGIC.to_vec();
+        header.extend_from_slice(&dict_len.to_le_bytes());
+        header.extend_from_slice(dict.as_bytes());
+        header
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.file.write_all(bytes)
+    }
+
+    pub fn write_u8(&mut self, value: u8) -> io::Result<()> {
+        self.file.write_all(&[value])
+    }
+
+    pub fn write_f64(&mut self, value: f64) -> io::Result<()> {
+        self.file.write_all(&value.to_le_bytes())
+    }
+
+    pub fn write_bool(&mut self, value: bool) -> io::Result<()> {
+        self.write_u8(value.into())
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
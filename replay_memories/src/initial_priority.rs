@@ -0,0 +1,66 @@
+use super::traits::fallback_priority;
+
+// The priority given to a transition when it is inserted, before the learner
+// has computed its td error
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InitialPriority {
+    // the maximum priority currently in memory
+    CurrentMax,
+    // the maximum priority ever stored in memory, as OpenAI baselines do
+    AllTimeMax,
+    Fixed(f64),
+    // the priority supplied along with the transition, usually computed by
+    // the actor. Transitions inserted without one fall back to CurrentMax
+    Supplied,
+}
+
+// Applies an InitialPriority policy, keeping track of the priorities it needs
+#[derive(Clone)]
+pub(crate) struct InitialPriorityTracker {
+    policy: InitialPriority,
+    max_priority_ever: Option<f64>,
+}
+
+impl InitialPriorityTracker {
+    pub fn new(policy: InitialPriority) -> Self {
+        Self {
+            policy,
+            max_priority_ever: None,
+        }
+    }
+    // the priorities of transitions that are no longer in memory are unknown,
+    // so the all-time maximum of a loaded memory starts from its current one
+    pub fn with_max_priority(policy: InitialPriority, max_priority: Option<f64>) -> Self {
+        Self {
+            policy,
+            max_priority_ever: max_priority,
+        }
+    }
+    pub fn policy(&self) -> InitialPriority {
+        self.policy
+    }
+    pub fn set_policy(&mut self, policy: InitialPriority) {
+        self.policy = policy;
+    }
+    // should be called with every priority that is stored in memory
+    pub fn observe(&mut self, priority: f64) {
+        if self
+            .max_priority_ever
+            .is_none_or(|max_priority| max_priority < priority)
+        {
+            self.max_priority_ever = Some(priority);
+        }
+    }
+    pub fn initial_priority(&self, current_max: Option<f64>, supplied: Option<f64>) -> f64 {
+        let priority = match self.policy {
+            InitialPriority::CurrentMax => current_max,
+            InitialPriority::AllTimeMax => self.max_priority_ever.or(current_max),
+            InitialPriority::Fixed(priority) => Some(priority),
+            InitialPriority::Supplied => supplied.or(current_max),
+        };
+        match priority {
+            Some(priority) if priority >= fallback_priority() => priority,
+            _ => fallback_priority(),
+        }
+    }
+}
@@ -1,10 +1,20 @@
+mod dataset_export;
+mod initial_priority;
 mod replay_prioritized;
 mod replay_queue;
 mod replay_remote;
 mod replay_ring;
+mod replay_sequence;
+mod replay_shared;
+mod traits;
 mod transition_saving;
 
+pub use initial_priority::InitialPriority;
+pub use priority_tree::Prioritization;
 pub use replay_prioritized::ReplayPrioritized;
 pub use replay_queue::ReplayQueue;
 pub use replay_remote::ReplayRemote;
 pub use replay_ring::ReplayRing;
+pub use replay_sequence::{ReplaySequence, SequenceBuilder, TransitionSequence};
+pub use replay_shared::ReplayShared;
+pub use traits::{PersistentMemory, PrioritizedMemory, ReplayMemory, SampleError, SampledBatch};
@@ -1,59 +1,92 @@
 mod priority_circ_buffer;
 
+use super::dataset_export;
+use super::initial_priority::{InitialPriority, InitialPriorityTracker};
+use super::traits::{
+    fallback_priority, priority_from_abs_td_error, PersistentMemory, PrioritizedMemory,
+    ReplayMemory, SampleError, SampledBatch,
+};
 use super::transition_saving;
+use file_io::LoadError;
 use priority_circ_buffer::PriorityCircBuffer;
-use replay_data::CompressedRcTransition;
+use priority_tree::Prioritization;
+use replay_data::{CompressedArcTransition, TransitionMetadata};
 use std::path::Path;
 
-const EPSILON: f64 = 0.001;
-
+// Alpha is only applied when sampling, so that it can be changed at any time
 pub struct ReplayPrioritized {
-    transitions: PriorityCircBuffer<f64, CompressedRcTransition>,
+    transitions: PriorityCircBuffer<f64, CompressedArcTransition>,
+    initial_priority: InitialPriorityTracker,
 }
 
 impl ReplayPrioritized {
-    fn add_transition_with_priority(&mut self, transition: CompressedRcTransition, priority: f64) {
+    fn add_transition_with_priority(
+        &mut self,
+        transition: CompressedArcTransition,
+        supplied_priority: Option<f64>,
+    ) {
+        let priority = self
+            .initial_priority
+            .initial_priority(self.transitions.max_priority(), supplied_priority);
+        self.initial_priority.observe(priority);
         self.transitions.push(priority, transition);
     }
-    fn initial_priority(&self) -> f64 {
-        // technically, here OpenAI baselines use the maximum priority over all
-        // transitions ever encountered as the placeholder priority, while this
-        // uses the maximum over transitions currently present in memory
-        let max_priority = self.transitions.max_priority();
-        match max_priority {
-            Some(max_priority) => {
-                if max_priority < EPSILON {
-                    EPSILON
-                } else {
-                    max_priority
-                }
-            }
-            None => EPSILON,
+    fn from_transitions(transitions: PriorityCircBuffer<f64, CompressedArcTransition>) -> Self {
+        let initial_priority = InitialPriorityTracker::with_max_priority(
+            InitialPriority::CurrentMax,
+            transitions.max_priority(),
+        );
+        Self {
+            transitions,
+            initial_priority,
         }
     }
     pub fn with_max_size(max_size: usize) -> Self {
-        Self {
-            transitions: PriorityCircBuffer::with_max_size(max_size),
-        }
+        Self::from_transitions(PriorityCircBuffer::with_max_size(max_size))
     }
-    pub fn update_priorities_with_td_errors(
-        &mut self,
-        indices: &[usize],
-        abs_td_errors: &[f64],
+    pub fn with_prioritization(
+        max_size: usize,
+        prioritization: Prioritization,
         alpha: f64,
-    ) {
-        for (index, abs_td_error) in indices.iter().zip(abs_td_errors.iter()) {
-            let priority = (abs_td_error + EPSILON).powf(alpha);
-            self.transitions.update_priority(*index, priority);
+    ) -> Self {
+        Self::from_transitions(PriorityCircBuffer::with_prioritization(
+            max_size,
+            prioritization,
+            alpha,
+        ))
+    }
+    pub fn initial_priority_policy(&self) -> InitialPriority {
+        self.initial_priority.policy()
+    }
+    // under InitialPriority::Supplied, transitions inserted without a td error
+    // get the maximum priority currently in memory
+    pub fn set_initial_priority_policy(&mut self, policy: InitialPriority) {
+        self.initial_priority.set_policy(policy);
+    }
+    pub fn alpha(&self) -> f64 {
+        self.transitions.exponent()
+    }
+    pub fn set_alpha(&mut self, alpha: f64) {
+        self.transitions.set_exponent(alpha);
+    }
+    pub fn update_priorities_with_td_errors(&mut self, indices: &[usize], abs_td_errors: &[f64]) {
+        let priorities: Vec<_> = indices
+            .iter()
+            .zip(abs_td_errors.iter())
+            .map(|(index, abs_td_error)| (*index, priority_from_abs_td_error(*abs_td_error)))
+            .collect();
+        for (_index, priority) in &priorities {
+            self.initial_priority.observe(*priority);
         }
+        self.transitions.update_priorities(&priorities);
     }
-    pub fn add_transition(&mut self, transition: CompressedRcTransition) {
-        self.add_transition_with_priority(transition, self.initial_priority());
+    pub fn add_transition(&mut self, transition: CompressedArcTransition) {
+        self.add_transition_with_priority(transition, None);
     }
     pub fn sample_batch(
         &self,
         batch_size: usize,
-    ) -> (Vec<usize>, Vec<f64>, Vec<&CompressedRcTransition>) {
+    ) -> (Vec<usize>, Vec<f64>, Vec<&CompressedArcTransition>) {
         let mut batch_indices = vec![];
         let mut batch_probabilities = vec![];
         let mut batch_transitions = vec![];
@@ -72,16 +105,117 @@ impl ReplayPrioritized {
         (batch_indices, batch_probabilities, batch_transitions)
     }
     pub fn min_probability(&self) -> f64 {
-        let min_priority = self.transitions.min_priority().unwrap_or(EPSILON);
+        let min_priority = self
+            .transitions
+            .min_priority()
+            .unwrap_or_else(fallback_priority);
         min_priority / self.transitions.total_priority()
     }
     pub fn len(&self) -> usize {
         self.transitions.len()
     }
+    pub fn max_size(&self) -> usize {
+        self.transitions.max_size()
+    }
+    // keeps the newest transitions that fit the new max size
+    pub fn resize(&mut self, max_size: usize) {
+        self.transitions.resize(max_size);
+    }
+    // iterates over (index, priority, transition) from oldest to newest
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = (usize, f64, &CompressedArcTransition)> {
+        self.transitions.iter()
+    }
     pub fn save<P: AsRef<Path>>(&self, path: P) {
         self.transitions.save(path);
     }
-    pub fn load<P: AsRef<Path>>(&mut self, path: P) {
-        self.transitions.load(path);
+    // see PriorityCircBuffer::snapshot
+    pub fn snapshot(&self) -> Self {
+        Self {
+            transitions: self.transitions.snapshot(),
+            initial_priority: self.initial_priority.clone(),
+        }
+    }
+    pub fn saved_snapshot(&self, snapshot: &Self) {
+        self.transitions.saved_snapshot(&snapshot.transitions);
+    }
+    pub fn load<P: AsRef<Path>>(&mut self, path: P) -> Result<(), LoadError> {
+        self.transitions.load(path)?;
+        self.initial_priority = InitialPriorityTracker::with_max_priority(
+            self.initial_priority.policy(),
+            self.transitions.max_priority(),
+        );
+        Ok(())
+    }
+    pub fn from_saved<P: AsRef<Path>>(path: P) -> Result<Self, LoadError> {
+        Ok(Self::from_transitions(PriorityCircBuffer::from_saved(
+            path,
+        )?))
+    }
+    pub fn export_dataset<P: AsRef<Path>>(&self, path: P, shard_len: usize) {
+        let transitions = self
+            .iter()
+            .map(|(_index, priority, transition)| (priority, transition));
+        dataset_export::export_dataset(path, transitions, shard_len);
+    }
+}
+
+impl ReplayMemory for ReplayPrioritized {
+    type Transition = CompressedArcTransition;
+    type Sampled<'a> = &'a CompressedArcTransition;
+
+    fn len(&self) -> usize {
+        self.transitions.len()
+    }
+    fn insert(
+        &mut self,
+        transition: CompressedArcTransition,
+        _metadata: Option<TransitionMetadata>,
+    ) {
+        self.add_transition(transition);
+    }
+    fn sample(
+        &self,
+        batch_size: usize,
+    ) -> Result<SampledBatch<&CompressedArcTransition>, SampleError> {
+        if self.len() < batch_size {
+            return Err(SampleError::NotEnoughTransitions);
+        }
+        let (indices, probabilities, transitions) = self.sample_batch(batch_size);
+        Ok(SampledBatch {
+            indices,
+            probabilities,
+            transitions,
+            min_probability: self.min_probability(),
+            memory_len: self.len(),
+        })
+    }
+}
+
+impl PrioritizedMemory for ReplayPrioritized {
+    fn insert_with_abs_td_error(
+        &mut self,
+        transition: CompressedArcTransition,
+        abs_td_error: f64,
+        _metadata: Option<TransitionMetadata>,
+    ) {
+        self.add_transition_with_priority(
+            transition,
+            Some(priority_from_abs_td_error(abs_td_error)),
+        );
+    }
+    fn update_priorities_with_td_errors(&mut self, indices: &[usize], abs_td_errors: &[f64]) {
+        ReplayPrioritized::update_priorities_with_td_errors(self, indices, abs_td_errors);
+    }
+    fn set_alpha(&mut self, alpha: f64) {
+        ReplayPrioritized::set_alpha(self, alpha);
+    }
+}
+
+impl PersistentMemory for ReplayPrioritized {
+    fn save<P: AsRef<Path>>(&self, path: P) {
+        ReplayPrioritized::save(self, path);
+    }
+    fn load<P: AsRef<Path>>(&mut self, path: P) -> Result<(), LoadError> {
+        ReplayPrioritized::load(self, path)
     }
 }
@@ -3,20 +3,40 @@ mod save_load;
 
 use super::transition_saving;
 use super::PriorityCircBuffer;
-use priority_tree::{Priority, PriorityTree};
+use super::SaveState;
+use priority_tree::{Priorities, Prioritization, Priority};
+use std::cell::RefCell;
 
 impl<P: Priority, V> PriorityCircBuffer<P, V> {
     pub fn with_max_size(max_size: usize) -> Self {
+        Self::with_prioritization(max_size, Prioritization::Proportional, 1.0)
+    }
+
+    pub fn with_prioritization(
+        max_size: usize,
+        prioritization: Prioritization,
+        exponent: f64,
+    ) -> Self {
+        Self::check_max_size(max_size);
         Self {
-            priorities: PriorityTree::with_leaf_count(max_size),
+            priorities: Priorities::with_leaf_count(prioritization, exponent, max_size),
             values: vec![],
             max_size,
             head: 0,
+            save_state: RefCell::new(SaveState::default()),
         }
     }
 
+    fn check_max_size(max_size: usize) {
+        assert!(
+            max_size > 0,
+            "PriorityCircBuffer needs a max size of at least 1"
+        );
+    }
+
     pub fn push(&mut self, priority: P, value: V) {
         self.update_priority(self.head, priority);
+        self.save_state.get_mut().mark_inserted(self.max_size);
         if self.head == self.values.len() {
             self.values.push(value);
         } else {
@@ -28,12 +48,75 @@ impl<P: Priority, V> PriorityCircBuffer<P, V> {
         }
     }
 
+    // changes the max size, keeping the newest values that fit
+    pub fn resize(&mut self, max_size: usize) {
+        if max_size == self.max_size {
+            return;
+        }
+        Self::check_max_size(max_size);
+        let len = self.len();
+        let dropped_len = len - len.min(max_size);
+        let priorities: Vec<_> = self
+            .iter()
+            .skip(dropped_len)
+            .map(|(_, priority, _)| priority)
+            .collect();
+        // until the buffer fills up, head is equal to its length, so rotating
+        // by head puts the oldest value first either way
+        self.values.rotate_left(self.head);
+        self.values.drain(..dropped_len);
+        self.priorities = Priorities::with_priorities(
+            self.priorities.prioritization(),
+            self.priorities.exponent(),
+            max_size,
+            priorities,
+        );
+        self.max_size = max_size;
+        self.head = self.values.len() % max_size;
+        // the values moved, so the next save can't be appended to the log of
+        // the previous one
+        *self.save_state.get_mut() = SaveState::default();
+    }
+
+    pub fn prioritization(&self) -> Prioritization {
+        self.priorities.prioritization()
+    }
+
+    pub fn set_prioritization(&mut self, prioritization: Prioritization) {
+        if prioritization == self.prioritization() {
+            return;
+        }
+        let priorities: Vec<_> = (0..self.max_size)
+            .map(|index| (index < self.len()).then(|| self.priorities.raw_priority(index)))
+            .collect();
+        self.priorities = Priorities::with_priorities(
+            prioritization,
+            self.priorities.exponent(),
+            self.max_size,
+            priorities,
+        );
+        // the saved priorities are in the format of the previous prioritization
+        *self.save_state.get_mut() = SaveState::default();
+    }
+
+    pub fn exponent(&self) -> f64 {
+        self.priorities.exponent()
+    }
+
+    // changes the exponent of the prioritization, which takes time linear in
+    // the max size
+    pub fn set_exponent(&mut self, exponent: f64) {
+        self.priorities.set_exponent(exponent);
+    }
+
     pub fn min_priority(&self) -> Option<P> {
         self.priorities.min()
     }
 
+    // refers to the priorities as they were passed to push or update_priority,
+    // as opposed to the priorities that sampling follows
     pub fn max_priority(&self) -> Option<P> {
-        self.priorities.max()
+        self.priorities.max_raw_priority()
     }
 
     pub fn total_priority(&self) -> P {
@@ -42,9 +125,38 @@ impl<P: Priority, V> PriorityCircBuffer<P, V> {
 
     pub fn update_priority(&mut self, index: usize, priority: P) {
         self.priorities.update(index, priority);
+        self.save_state.get_mut().mark_updated(index);
+    }
+
+    pub fn update_priorities(&mut self, priorities: &[(usize, P)]) {
+        self.priorities.update_many(priorities);
+        let save_state = self.save_state.get_mut();
+        for (index, _) in priorities {
+            save_state.mark_updated(*index);
+        }
     }
 
     pub fn len(&self) -> usize {
         self.values.len()
     }
+
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    // iterates over the stored values from oldest to newest, along with their
+    // priorities as they were passed to push or update_priority
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = (usize, P, &V)> {
+        // until the buffer fills up, head is equal to its length, so the
+        // oldest value is at index 0 either way
+        let len = self.len();
+        (0..len).map(move |offset| {
+            let index = (self.head + offset) % len;
+            (
+                index,
+                self.priorities.raw_priority(index),
+                &self.values[index],
+            )
+        })
+    }
 }
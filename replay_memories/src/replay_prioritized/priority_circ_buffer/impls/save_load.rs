@@ -1,12 +1,143 @@
+// A saved buffer consists of a base snapshot of the whole buffer followed by a
+// log of segments, each holding the changes made between two saves: the
+// transitions inserted since the previous save, the priorities updated since
+// then and the new head. Saving into the checkpoint that the buffer was last
+// saved to or loaded from appends a segment to its log, which is much cheaper
+// than rewriting the base. Once the log grows long, or when the buffer has
+// been entirely overwritten since the previous save, the next save compacts
+// everything into a new base snapshot
+//
+// When saving into a checkpoint's staging directory, the base and the
+// existing log segments are hard-linked from the committed checkpoint
+use super::super::save_state::{SaveState, SavedSnapshot};
 use super::transition_saving::{load_transitions, save_transitions};
 use super::PriorityCircBuffer;
-use file_io::{create_file_buf_write, open_file_buf_read};
-use replay_data::CompressedRcTransition;
-use std::path::Path;
+use file_io::{checkpoint, create_file_buf_write, load_bincode, LoadError, Manifest};
+use replay_data::CompressedArcTransition;
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-impl PriorityCircBuffer<f64, CompressedRcTransition> {
+const COMPONENT: &str = "replay_prioritized";
+const FORMAT_VERSION: u32 = 5;
+const MAX_LOG_LEN: usize = 16;
+const BASE_FILE_NAMES: [&str; 5] = ["max_size", "frames", "transitions", "head", "priorities"];
+const LOG_DIR_NAME: &str = "log";
+
+fn segment_path(path: &Path, segment: usize) -> PathBuf {
+    path.join(LOG_DIR_NAME).join(format!("{:06}", segment))
+}
+
+fn config_value<T: FromStr>(manifest: &Manifest, key: &str) -> Result<T, LoadError> {
+    manifest
+        .config(key)
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| LoadError::Invalid(format!("manifest has no valid {} entry", key)))
+}
+
+// links the base and the first log_len log segments of the checkpoint at
+// from_path into to_path
+fn link_snapshot(from_path: &Path, to_path: &Path, log_len: usize) {
+    for file_name in BASE_FILE_NAMES {
+        fs::hard_link(from_path.join(file_name), to_path.join(file_name)).unwrap();
+    }
+    for segment in 0..log_len {
+        let from_segment_path = segment_path(from_path, segment);
+        let to_segment_path = segment_path(to_path, segment);
+        fs::create_dir_all(&to_segment_path).unwrap();
+        for entry in fs::read_dir(&from_segment_path).unwrap() {
+            let entry = entry.unwrap();
+            fs::hard_link(entry.path(), to_segment_path.join(entry.file_name())).unwrap();
+        }
+    }
+}
+
+impl PriorityCircBuffer<f64, CompressedArcTransition> {
     pub fn save<P: AsRef<Path>>(&self, path: P) {
         let path = path.as_ref();
+        // the checkpoint that a staged save will replace is where the
+        // previous save (if any) can be found
+        let committed_path = checkpoint::committed_path(path).unwrap_or_else(|| path.into());
+        let mut save_state = self.save_state.borrow_mut();
+        let snapshot = match self.appendable_snapshot(&save_state, &committed_path) {
+            Some(snapshot) => {
+                if committed_path != path {
+                    link_snapshot(&committed_path, path, snapshot.log_len);
+                }
+                self.save_segment(&segment_path(path, snapshot.log_len), &save_state);
+                SavedSnapshot {
+                    log_len: snapshot.log_len + 1,
+                    ..snapshot
+                }
+            }
+            None => {
+                self.save_base(path);
+                SavedSnapshot {
+                    path: committed_path,
+                    snapshot_id: rand::random(),
+                    log_len: 0,
+                }
+            }
+        };
+        let mut manifest = Manifest::new();
+        manifest.add_component(COMPONENT, FORMAT_VERSION);
+        manifest.add_config("max_size", self.max_size);
+        manifest.add_config("snapshot_id", snapshot.snapshot_id);
+        manifest.add_config("log_len", snapshot.log_len);
+        // the exponent may have changed since the base was saved
+        manifest.add_config("exponent", self.exponent());
+        manifest.save(path).unwrap();
+        *save_state = SaveState::saved(snapshot);
+    }
+    // a copy of the buffer that can be saved while the buffer itself keeps
+    // changing. The changes tracked since the previous save move to the copy,
+    // so once the copy is saved, saved_snapshot should be called with it for
+    // the next save of the buffer to append to the copy's checkpoint
+    pub fn snapshot(&self) -> Self {
+        Self {
+            priorities: self.priorities.clone(),
+            values: self.values.clone(),
+            max_size: self.max_size,
+            head: self.head,
+            save_state: RefCell::new(self.save_state.take()),
+        }
+    }
+    pub fn saved_snapshot(&self, snapshot: &Self) {
+        let mut save_state = self.save_state.borrow_mut();
+        // the buffer may have been saved or loaded since the snapshot was
+        // taken, in which case its own snapshot is more recent
+        if save_state.snapshot.is_none() {
+            save_state.snapshot = snapshot.save_state.borrow().snapshot.clone();
+        }
+    }
+    // returns the snapshot that the next save may append a segment to, if any
+    fn appendable_snapshot(
+        &self,
+        save_state: &SaveState,
+        committed_path: &Path,
+    ) -> Option<SavedSnapshot> {
+        let snapshot = save_state.snapshot.as_ref()?;
+        if snapshot.path != committed_path
+            || snapshot.log_len >= MAX_LOG_LEN
+            || save_state.inserted_count >= self.len()
+        {
+            return None;
+        }
+        // the checkpoint on disk may have been replaced or modified since
+        // the buffer was saved to it
+        let manifest = Manifest::load(committed_path).ok()?;
+        let snapshot_id: u64 = config_value(&manifest, "snapshot_id").ok()?;
+        let log_len: usize = config_value(&manifest, "log_len").ok()?;
+        (snapshot_id == snapshot.snapshot_id && log_len == snapshot.log_len)
+            .then(|| snapshot.clone())
+    }
+    fn save_base(&self, path: &Path) {
+        // a previous log would be meaningless on top of the new base
+        let log_path = path.join(LOG_DIR_NAME);
+        if log_path.exists() {
+            fs::remove_dir_all(log_path).unwrap();
+        }
         let max_size_file = create_file_buf_write(path.join("max_size")).unwrap();
         bincode::serialize_into(max_size_file, &self.max_size).unwrap();
         save_transitions(path, &self.values);
@@ -15,14 +146,333 @@ impl PriorityCircBuffer<f64, CompressedRcTransition> {
         let priorities_file = create_file_buf_write(path.join("priorities")).unwrap();
         bincode::serialize_into(priorities_file, &self.priorities).unwrap();
     }
-    pub fn load<P: AsRef<Path>>(&mut self, path: P) {
+    fn save_segment(&self, segment_path: &Path, save_state: &SaveState) {
+        fs::create_dir_all(segment_path).unwrap();
+        let inserted_count = save_state.inserted_count;
+        let inserted_indices: Vec<_> = (0..inserted_count)
+            .map(|offset| (self.head + self.max_size - inserted_count + offset) % self.max_size)
+            .collect();
+        save_transitions(
+            segment_path,
+            inserted_indices.iter().map(|index| &self.values[*index]),
+        );
+        let indices_file = create_file_buf_write(segment_path.join("indices")).unwrap();
+        bincode::serialize_into(indices_file, &inserted_indices).unwrap();
+        let mut updated_indices: Vec<_> = save_state.updated_indices.iter().copied().collect();
+        updated_indices.sort_unstable();
+        let updated_priorities: Vec<_> = updated_indices
+            .into_iter()
+            .map(|index| (index, self.priorities.raw_priority(index)))
+            .collect();
+        let priorities_file = create_file_buf_write(segment_path.join("priorities")).unwrap();
+        bincode::serialize_into(priorities_file, &updated_priorities).unwrap();
+        let head_file = create_file_buf_write(segment_path.join("head")).unwrap();
+        bincode::serialize_into(head_file, &self.head).unwrap();
+    }
+    // unlike from_saved, keeps the current max size, prioritization and
+    // exponent, even if the buffer was saved with different ones
+    pub fn load<P: AsRef<Path>>(&mut self, path: P) -> Result<(), LoadError> {
+        let max_size = self.max_size;
+        let prioritization = self.prioritization();
+        let exponent = self.exponent();
+        *self = Self::from_saved(path)?;
+        self.resize(max_size);
+        self.set_prioritization(prioritization);
+        self.set_exponent(exponent);
+        Ok(())
+    }
+    pub fn from_saved<P: AsRef<Path>>(path: P) -> Result<Self, LoadError> {
         let path = path.as_ref();
-        let max_size_file = open_file_buf_read(path.join("max_size")).unwrap();
-        self.max_size = bincode::deserialize_from(max_size_file).unwrap();
-        self.values = load_transitions(path, self.max_size);
-        let head_file = open_file_buf_read(path.join("head")).unwrap();
-        self.head = bincode::deserialize_from(head_file).unwrap();
-        let priorities_file = open_file_buf_read(path.join("priorities")).unwrap();
-        self.priorities = bincode::deserialize_from(priorities_file).unwrap();
+        let manifest = Manifest::load(path)?;
+        manifest.check_component(COMPONENT, FORMAT_VERSION)?;
+        let snapshot_id = config_value(&manifest, "snapshot_id")?;
+        let log_len = config_value(&manifest, "log_len")?;
+        let exponent = config_value(&manifest, "exponent")?;
+        let max_size = load_bincode(path.join("max_size"))?;
+        let values = load_transitions(path, max_size)?;
+        let head = load_bincode(path.join("head"))?;
+        let priorities = load_bincode(path.join("priorities"))?;
+        let mut buffer = Self {
+            priorities,
+            values,
+            max_size,
+            head,
+            save_state: RefCell::new(SaveState::default()),
+        };
+        buffer.check_bounds()?;
+        for segment in 0..log_len {
+            buffer.load_segment(&segment_path(path, segment))?;
+            buffer.check_bounds()?;
+        }
+        buffer.set_exponent(exponent);
+        // the snapshot is identified by the path of the checkpoint it was
+        // loaded from, even if the load path points into a staging directory
+        let committed_path = checkpoint::committed_path(path).unwrap_or_else(|| path.into());
+        *buffer.save_state.get_mut() = SaveState::saved(SavedSnapshot {
+            path: committed_path,
+            snapshot_id,
+            log_len,
+        });
+        Ok(buffer)
+    }
+    fn load_segment(&mut self, segment_path: &Path) -> Result<(), LoadError> {
+        let inserted_indices: Vec<usize> = load_bincode(segment_path.join("indices"))?;
+        // frames are only deduplicated within a segment, so a frame shared
+        // with a transition from an earlier segment is loaded twice
+        let inserted_values = load_transitions(segment_path, inserted_indices.len())?;
+        if inserted_values.len() != inserted_indices.len() {
+            return Err(LoadError::Invalid(format!(
+                "log segment {} holds {} transitions but {} indices",
+                segment_path.display(),
+                inserted_values.len(),
+                inserted_indices.len()
+            )));
+        }
+        for (index, value) in inserted_indices.into_iter().zip(inserted_values) {
+            if index == self.values.len() && index < self.max_size {
+                self.values.push(value);
+            } else if index < self.values.len() {
+                self.values[index] = value;
+            } else {
+                return Err(LoadError::Invalid(format!(
+                    "log segment {} inserts at index {} into {} transitions",
+                    segment_path.display(),
+                    index,
+                    self.values.len()
+                )));
+            }
+        }
+        let updated_priorities: Vec<(usize, f64)> = load_bincode(segment_path.join("priorities"))?;
+        for (index, priority) in updated_priorities {
+            if index >= self.max_size {
+                return Err(LoadError::Invalid(format!(
+                    "log segment {} updates the priority at index {} beyond a max size of {}",
+                    segment_path.display(),
+                    index,
+                    self.max_size
+                )));
+            }
+            self.priorities.update(index, priority);
+        }
+        self.head = load_bincode(segment_path.join("head"))?;
+        Ok(())
+    }
+    fn check_bounds(&self) -> Result<(), LoadError> {
+        if self.max_size == 0 {
+            return Err(LoadError::Invalid(
+                "the saved buffer has a max size of 0".to_string(),
+            ));
+        }
+        if self.values.len() > self.max_size || self.head > self.values.len() {
+            return Err(LoadError::Invalid(format!(
+                "{} transitions with head {} don't fit a max size of {}",
+                self.values.len(),
+                self.head,
+                self.max_size
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageOwned, ImageOwned2};
+    use replay_data::CompressedImageOwned2;
+    use std::sync::Arc;
+
+    type Buffer = PriorityCircBuffer<f64, CompressedArcTransition>;
+
+    // every transition is told apart by its reward
+    fn transition(reward: u8) -> CompressedArcTransition {
+        let frame = |value: u8| {
+            let image = ImageOwned2::new(2, 2, vec![value; 8]);
+            Arc::new(CompressedImageOwned2::from(&image))
+        };
+        CompressedArcTransition {
+            state: [frame(reward), frame(reward.wrapping_add(1))].into(),
+            next_state: [frame(reward.wrapping_add(1)), frame(reward.wrapping_add(2))].into(),
+            action: reward % 3,
+            reward: f64::from(reward),
+            terminated: false,
+        }
+    }
+
+    fn push(buffer: &mut Buffer, rewards: std::ops::Range<u8>) {
+        for reward in rewards {
+            buffer.push(1.0 + f64::from(reward) / 10.0, transition(reward));
+        }
+    }
+
+    // a checkpoint directory of its own for each test, which the tests run in
+    // parallel
+    fn checkpoint_target(test_name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "replay_prioritized_{}_{}",
+            test_name,
+            std::process::id()
+        ));
+        if dir.exists() {
+            fs::remove_dir_all(&dir).unwrap();
+        }
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("checkpoint")
+    }
+
+    // saves the way the binaries do, through a staging directory
+    fn save_checkpoint(buffer: &Buffer, target: &Path) -> usize {
+        let staging_path = checkpoint::begin(target).unwrap();
+        buffer.save(&staging_path);
+        checkpoint::commit(target).unwrap();
+        let manifest = Manifest::load(target).unwrap();
+        config_value(&manifest, "log_len").unwrap()
+    }
+
+    fn pixels(transition: &CompressedArcTransition) -> Vec<Vec<u8>> {
+        let frames = transition.state.frames().iter();
+        let next_frames = transition.next_state.frames().iter();
+        frames
+            .chain(next_frames)
+            .map(|frame| ImageOwned2::from(frame.as_ref()).as_ref().data().to_vec())
+            .collect()
+    }
+
+    fn assert_same(loaded: &Buffer, buffer: &Buffer) {
+        assert_eq!(loaded.len(), buffer.len());
+        assert_eq!(loaded.max_size, buffer.max_size);
+        assert_eq!(loaded.head, buffer.head);
+        assert_eq!(loaded.exponent(), buffer.exponent());
+        for ((loaded_index, loaded_priority, loaded_value), (index, priority, value)) in
+            loaded.iter().zip(buffer.iter())
+        {
+            assert_eq!(loaded_index, index);
+            assert_eq!(loaded_priority, priority);
+            assert_eq!(loaded_value.reward, value.reward);
+            assert_eq!(loaded_value.action, value.action);
+            assert_eq!(pixels(loaded_value), pixels(value));
+        }
+        assert_eq!(loaded.total_priority(), buffer.total_priority());
+    }
+
+    #[test]
+    fn incremental_saves_load_like_the_buffer() {
+        let target = checkpoint_target("incremental");
+        let mut buffer = Buffer::with_max_size(8);
+        push(&mut buffer, 0..5);
+        assert_eq!(save_checkpoint(&buffer, &target), 0);
+
+        // wraps around, evicting the oldest transitions
+        push(&mut buffer, 5..11);
+        buffer.update_priority(4, 7.5);
+        buffer.update_priorities(&[(0, 0.25), (6, 3.0)]);
+        assert_eq!(save_checkpoint(&buffer, &target), 1);
+        assert_same(&Buffer::from_saved(&target).unwrap(), &buffer);
+
+        buffer.update_priority(1, 0.5);
+        assert_eq!(save_checkpoint(&buffer, &target), 2);
+        push(&mut buffer, 11..13);
+        assert_eq!(save_checkpoint(&buffer, &target), 3);
+        let mut loaded = Buffer::from_saved(&target).unwrap();
+        assert_same(&loaded, &buffer);
+
+        // the loaded buffer keeps appending to the log it was loaded from
+        push(&mut loaded, 13..14);
+        push(&mut buffer, 13..14);
+        assert_eq!(save_checkpoint(&loaded, &target), 4);
+        assert_same(&Buffer::from_saved(&target).unwrap(), &buffer);
+    }
+
+    #[test]
+    fn long_logs_are_compacted() {
+        let target = checkpoint_target("compaction");
+        let mut buffer = Buffer::with_max_size(64);
+        push(&mut buffer, 0..4);
+        assert_eq!(save_checkpoint(&buffer, &target), 0);
+        for (log_len, reward) in (1..=MAX_LOG_LEN).zip(4..) {
+            push(&mut buffer, reward..reward + 1);
+            assert_eq!(save_checkpoint(&buffer, &target), log_len);
+        }
+        push(&mut buffer, 100..101);
+        assert_eq!(save_checkpoint(&buffer, &target), 0);
+        assert!(!target.join(LOG_DIR_NAME).exists());
+        assert_same(&Buffer::from_saved(&target).unwrap(), &buffer);
+    }
+
+    #[test]
+    fn overwritten_buffers_are_saved_as_a_base() {
+        let target = checkpoint_target("overwritten");
+        let mut buffer = Buffer::with_max_size(4);
+        push(&mut buffer, 0..4);
+        assert_eq!(save_checkpoint(&buffer, &target), 0);
+        push(&mut buffer, 4..8);
+        assert_eq!(save_checkpoint(&buffer, &target), 0);
+        assert_same(&Buffer::from_saved(&target).unwrap(), &buffer);
+    }
+
+    #[test]
+    fn saves_after_a_resize_are_saved_as_a_base() {
+        let target = checkpoint_target("resize");
+        let mut buffer = Buffer::with_max_size(8);
+        push(&mut buffer, 0..10);
+        assert_eq!(save_checkpoint(&buffer, &target), 0);
+        push(&mut buffer, 10..11);
+        buffer.resize(5);
+        assert_eq!(save_checkpoint(&buffer, &target), 0);
+        assert_same(&Buffer::from_saved(&target).unwrap(), &buffer);
+
+        push(&mut buffer, 11..12);
+        buffer.set_prioritization(priority_tree::Prioritization::RankBased);
+        assert_eq!(save_checkpoint(&buffer, &target), 0);
+        let loaded = Buffer::from_saved(&target).unwrap();
+        assert_eq!(loaded.prioritization(), buffer.prioritization());
+        assert_same(&loaded, &buffer);
+    }
+
+    #[test]
+    fn replaced_checkpoints_are_not_appended_to() {
+        let target = checkpoint_target("replaced");
+        let mut buffer = Buffer::with_max_size(8);
+        push(&mut buffer, 0..3);
+        assert_eq!(save_checkpoint(&buffer, &target), 0);
+
+        // another buffer takes over the checkpoint
+        let mut other = Buffer::with_max_size(8);
+        push(&mut other, 50..55);
+        assert_eq!(save_checkpoint(&other, &target), 0);
+
+        push(&mut buffer, 3..4);
+        assert_eq!(save_checkpoint(&buffer, &target), 0);
+        assert_same(&Buffer::from_saved(&target).unwrap(), &buffer);
+
+        // the same goes for a checkpoint whose manifest was rewritten
+        push(&mut buffer, 4..5);
+        assert_eq!(save_checkpoint(&buffer, &target), 1);
+        let mut manifest = Manifest::load(&target).unwrap();
+        manifest.add_config("snapshot_id", 0);
+        manifest.save(&target).unwrap();
+        push(&mut buffer, 5..6);
+        assert_eq!(save_checkpoint(&buffer, &target), 0);
+        assert_same(&Buffer::from_saved(&target).unwrap(), &buffer);
+    }
+
+    #[test]
+    fn saved_snapshots_are_appended_to() {
+        let target = checkpoint_target("snapshot");
+        let mut buffer = Buffer::with_max_size(16);
+        push(&mut buffer, 0..4);
+        assert_eq!(save_checkpoint(&buffer, &target), 0);
+        push(&mut buffer, 4..6);
+        let snapshot = buffer.snapshot();
+        // changes made while the snapshot is saved are left for the next save
+        push(&mut buffer, 6..7);
+        buffer.update_priority(0, 9.0);
+        assert_eq!(save_checkpoint(&snapshot, &target), 1);
+        buffer.saved_snapshot(&snapshot);
+        assert_same(&Buffer::from_saved(&target).unwrap(), &snapshot);
+
+        push(&mut buffer, 7..8);
+        assert_eq!(save_checkpoint(&buffer, &target), 2);
+        assert_same(&Buffer::from_saved(&target).unwrap(), &buffer);
     }
 }
@@ -1,11 +1,15 @@
 mod impls;
+mod save_state;
 
 use super::transition_saving;
-use priority_tree::{Priority, PriorityTree};
+use priority_tree::{Priorities, Priority};
+use save_state::SaveState;
+use std::cell::RefCell;
 
 pub struct PriorityCircBuffer<P: Priority, V> {
-    priorities: PriorityTree<P>,
+    priorities: Priorities<P>,
     values: Vec<V>,
     max_size: usize,
     head: usize,
+    save_state: RefCell<SaveState>,
 }
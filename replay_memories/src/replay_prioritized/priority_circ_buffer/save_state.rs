@@ -0,0 +1,39 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+// Identifies the checkpoint that a buffer was last saved to or loaded from
+#[derive(Clone)]
+pub struct SavedSnapshot {
+    pub path: PathBuf,
+    pub snapshot_id: u64,
+    pub log_len: usize,
+}
+
+// Tracks the changes made to a buffer since it was last saved, so that the
+// next save may append just those changes to the log of the previous
+// checkpoint instead of rewriting the whole buffer
+#[derive(Default)]
+pub struct SaveState {
+    pub snapshot: Option<SavedSnapshot>,
+    // the number of values pushed since the last save. Pushes happen at
+    // consecutive indices, so the pushed values are the inserted_count ones
+    // right before head. Capped at the buffer's max size
+    pub inserted_count: usize,
+    pub updated_indices: HashSet<usize>,
+}
+
+impl SaveState {
+    pub fn saved(snapshot: SavedSnapshot) -> Self {
+        Self {
+            snapshot: Some(snapshot),
+            inserted_count: 0,
+            updated_indices: HashSet::new(),
+        }
+    }
+    pub fn mark_inserted(&mut self, max_size: usize) {
+        self.inserted_count = (self.inserted_count + 1).min(max_size);
+    }
+    pub fn mark_updated(&mut self, index: usize) {
+        self.updated_indices.insert(index);
+    }
+}
@@ -1,12 +1,17 @@
+use super::traits::{PersistentMemory, ReplayMemory, SampleError, SampledBatch};
 use super::transition_saving::{load_transitions, save_transitions};
-use file_io::{create_file_buf_write, open_file_buf_read};
-use rand::prelude::{IteratorRandom, SliceRandom};
-use replay_data::CompressedRcTransition;
+use file_io::{create_file_buf_write, load_bincode, LoadError, Manifest};
+use rand::seq::index;
+use rand::Rng;
+use replay_data::{CompressedArcTransition, TransitionMetadata};
 use std::collections::VecDeque;
 use std::path::Path;
 
+const COMPONENT: &str = "replay_queue";
+const FORMAT_VERSION: u32 = 2;
+
 pub struct ReplayQueue {
-    transitions: VecDeque<CompressedRcTransition>,
+    transitions: VecDeque<CompressedArcTransition>,
     max_size: usize,
 }
 
@@ -17,19 +22,32 @@ impl ReplayQueue {
             max_size,
         }
     }
-    pub fn add_transition(&mut self, transition: CompressedRcTransition) {
+    pub fn add_transition(&mut self, transition: CompressedArcTransition) {
         if self.transitions.len() >= self.max_size {
             self.transitions.pop_front();
         }
         self.transitions.push_back(transition);
     }
-    pub fn sample_batch(&self, batch_size: usize) -> Vec<&CompressedRcTransition> {
-        let mut batch = self
-            .transitions
-            .iter()
-            .choose_multiple(&mut rand::thread_rng(), batch_size);
-        batch.shuffle(&mut rand::thread_rng());
-        batch
+    // samples min(batch_size, len) distinct transitions in random order. The
+    // transitions are picked by index, so the cost doesn't depend on len
+    pub fn sample_batch(&self, batch_size: usize) -> Vec<&CompressedArcTransition> {
+        let batch_size = batch_size.min(self.transitions.len());
+        index::sample(&mut rand::thread_rng(), self.transitions.len(), batch_size)
+            .into_iter()
+            .map(|index| &self.transitions[index])
+            .collect()
+    }
+    pub fn sample_batch_with_replacement(
+        &self,
+        batch_size: usize,
+    ) -> Vec<&CompressedArcTransition> {
+        if self.transitions.is_empty() {
+            return vec![];
+        }
+        let mut rng = rand::thread_rng();
+        (0..batch_size)
+            .map(|_| &self.transitions[rng.gen_range(0..self.transitions.len())])
+            .collect()
     }
     pub fn len(&self) -> usize {
         self.transitions.len()
@@ -39,12 +57,63 @@ impl ReplayQueue {
         let max_size_file = create_file_buf_write(path.join("max_size")).unwrap();
         bincode::serialize_into(max_size_file, &self.max_size).unwrap();
         save_transitions(path, &self.transitions);
+        let mut manifest = Manifest::new();
+        manifest.add_component(COMPONENT, FORMAT_VERSION);
+        manifest.add_config("max_size", self.max_size);
+        manifest.save(path).unwrap();
     }
-    pub fn load<P: AsRef<Path>>(&mut self, path: P) {
+    pub fn load<P: AsRef<Path>>(&mut self, path: P) -> Result<(), LoadError> {
         let path = path.as_ref();
-        let max_size_file = open_file_buf_read(path.join("max_size")).unwrap();
-        self.max_size = bincode::deserialize_from(max_size_file).unwrap();
-        let transitions = load_transitions(path, self.max_size);
+        let manifest = Manifest::load(path)?;
+        manifest.check_component(COMPONENT, FORMAT_VERSION)?;
+        self.max_size = load_bincode(path.join("max_size"))?;
+        let transitions = load_transitions(path, self.max_size)?;
         self.transitions = transitions.into();
+        Ok(())
+    }
+}
+
+impl ReplayMemory for ReplayQueue {
+    type Transition = CompressedArcTransition;
+    type Sampled<'a> = &'a CompressedArcTransition;
+
+    fn len(&self) -> usize {
+        self.transitions.len()
+    }
+    fn insert(
+        &mut self,
+        transition: CompressedArcTransition,
+        _metadata: Option<TransitionMetadata>,
+    ) {
+        self.add_transition(transition);
+    }
+    fn sample(
+        &self,
+        batch_size: usize,
+    ) -> Result<SampledBatch<&CompressedArcTransition>, SampleError> {
+        if self.len() < batch_size {
+            return Err(SampleError::NotEnoughTransitions);
+        }
+        let indices = index::sample(&mut rand::thread_rng(), self.len(), batch_size).into_vec();
+        let probability = 1.0 / self.len() as f64;
+        Ok(SampledBatch {
+            transitions: indices
+                .iter()
+                .map(|index| &self.transitions[*index])
+                .collect(),
+            probabilities: vec![probability; batch_size],
+            indices,
+            min_probability: probability,
+            memory_len: self.len(),
+        })
+    }
+}
+
+impl PersistentMemory for ReplayQueue {
+    fn save<P: AsRef<Path>>(&self, path: P) {
+        ReplayQueue::save(self, path);
+    }
+    fn load<P: AsRef<Path>>(&mut self, path: P) -> Result<(), LoadError> {
+        ReplayQueue::load(self, path)
     }
 }
@@ -1,21 +1,28 @@
 mod replay_client;
 
-use packets::{Insertion, PriorityUpdate, SampleBatchResult};
+use super::traits::{
+    priority_from_abs_td_error, PersistentMemory, PrioritizedMemory, ReplayMemory, SampleError,
+    SampledBatch,
+};
+use file_io::LoadError;
+use packets::{
+    FetchTransitionsReply, Insertion, PriorityUpdate, ReplayStats, SampleBatchErrorKind,
+    SampleBatchReply, SampleBatchResult,
+};
 use replay_client::ReplayClient;
-use replay_data::CompressedTransition;
-use std::mem;
+use replay_data::{CompressedTransition, TransitionFilter, TransitionMetadata};
 use std::net::SocketAddr;
 use std::path::Path;
 
+// Every call makes a request to the replay server, so transitions should be
+// inserted in batches rather than one by one
 pub struct ReplayRemote {
-    insertion_batch: Vec<Insertion>,
     client: ReplayClient,
 }
 
 impl ReplayRemote {
     pub fn new(replay_server_addr: SocketAddr) -> Self {
         Self {
-            insertion_batch: vec![],
             client: ReplayClient::new(replay_server_addr),
         }
     }
@@ -25,29 +32,113 @@ impl ReplayRemote {
     pub fn update_priorities(&mut self, batch: Vec<PriorityUpdate>) {
         self.client.update_priorities(batch)
     }
-    pub fn add_transition_with_priority(
+    pub fn set_alpha(&mut self, alpha: f64) {
+        self.client.set_alpha(alpha)
+    }
+    pub fn stats(&self) -> ReplayStats {
+        self.client.stats()
+    }
+    pub fn fetch(&self, filter: TransitionFilter) -> FetchTransitionsReply {
+        self.client.fetch(filter)
+    }
+    // returns the number of deleted transitions
+    pub fn delete(&mut self, filter: TransitionFilter) -> usize {
+        self.client.delete(filter)
+    }
+    pub fn sample_batch(&self, batch_len: usize) -> SampleBatchResult {
+        self.client.sample_batch(batch_len)
+    }
+}
+
+impl ReplayMemory for ReplayRemote {
+    type Transition = CompressedTransition;
+    type Sampled<'a> = CompressedTransition;
+
+    fn len(&self) -> usize {
+        self.client.len()
+    }
+    // leaves the priority for the replay server to assign
+    fn insert(&mut self, transition: CompressedTransition, metadata: Option<TransitionMetadata>) {
+        self.insert_batch(vec![(transition, metadata)]);
+    }
+    fn insert_batch(&mut self, batch: Vec<(CompressedTransition, Option<TransitionMetadata>)>) {
+        let batch = batch
+            .into_iter()
+            .map(|(transition, metadata)| Insertion {
+                priority: None,
+                transition,
+                metadata,
+            })
+            .collect();
+        self.client.insert(batch);
+    }
+    fn sample(&self, batch_size: usize) -> Result<SampledBatch<CompressedTransition>, SampleError> {
+        match self.sample_batch(batch_size) {
+            Ok(SampleBatchReply {
+                batch: (indices, probabilities, transitions),
+                min_probability,
+                replay_len,
+            }) => Ok(SampledBatch {
+                indices,
+                probabilities,
+                transitions,
+                min_probability,
+                memory_len: replay_len,
+            }),
+            Err(SampleBatchErrorKind::NotEnoughTransitions) => {
+                Err(SampleError::NotEnoughTransitions)
+            }
+            Err(SampleBatchErrorKind::RateLimited) => Err(SampleError::RateLimited),
+        }
+    }
+}
+
+impl PrioritizedMemory for ReplayRemote {
+    fn insert_with_abs_td_error(
         &mut self,
         transition: CompressedTransition,
-        priority: f64,
+        abs_td_error: f64,
+        metadata: Option<TransitionMetadata>,
     ) {
-        const INSERTION_BATCH_LEN: usize = 50;
-        let insertion = Insertion {
-            priority,
-            transition,
-        };
-        self.insertion_batch.push(insertion);
-        if self.insertion_batch.len() >= INSERTION_BATCH_LEN {
-            let insertion_batch = mem::replace(&mut self.insertion_batch, vec![]);
-            self.client.insert(insertion_batch);
-        }
+        self.insert_batch_with_abs_td_errors(vec![(transition, abs_td_error, metadata)]);
     }
-    pub fn sample_batch(&self, batch_len: usize) -> SampleBatchResult {
-        self.client.sample_batch(batch_len)
+    fn insert_batch_with_abs_td_errors(
+        &mut self,
+        batch: Vec<(CompressedTransition, f64, Option<TransitionMetadata>)>,
+    ) {
+        let batch = batch
+            .into_iter()
+            .map(|(transition, abs_td_error, metadata)| Insertion {
+                priority: Some(priority_from_abs_td_error(abs_td_error)),
+                transition,
+                metadata,
+            })
+            .collect();
+        self.client.insert(batch);
     }
-    pub fn save<P: AsRef<Path>>(&self, _path: P) {
-        // TODO: might want to save the members of this struct here
+    fn update_priorities_with_td_errors(&mut self, indices: &[usize], abs_td_errors: &[f64]) {
+        let batch = indices
+            .iter()
+            .zip(abs_td_errors)
+            .map(|(index, abs_td_error)| PriorityUpdate {
+                index: *index,
+                priority: priority_from_abs_td_error(*abs_td_error),
+            })
+            .collect();
+        self.update_priorities(batch);
     }
-    pub fn load<P: AsRef<Path>>(&mut self, _path: P) {
-        // TODO: might want to load the members of this struct here
+    fn set_alpha(&mut self, alpha: f64) {
+        ReplayRemote::set_alpha(self, alpha);
+    }
+}
+
+// The transitions belong to the replay server, which outlives the processes
+// that share them, and nothing else is kept on this side. Saving and loading
+// are therefore no-ops, which means that the transitions aren't part of an
+// actor's or learner's checkpoint
+impl PersistentMemory for ReplayRemote {
+    fn save<P: AsRef<Path>>(&self, _path: P) {}
+    fn load<P: AsRef<Path>>(&mut self, _path: P) -> Result<(), LoadError> {
+        Ok(())
     }
 }
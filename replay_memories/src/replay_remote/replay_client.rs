@@ -1,4 +1,8 @@
-use packets::{Insertion, PriorityUpdate, ReplayRequest, SampleBatchResult};
+use packets::{
+    FetchTransitionsReply, InsertBatchErrorKind, InsertBatchResult, Insertion, PriorityUpdate,
+    ReplayRequest, ReplayStats, SampleBatchResult,
+};
+use replay_data::TransitionFilter;
 use std::net::{SocketAddr, TcpStream};
 
 pub struct ReplayClient {
@@ -29,8 +33,8 @@ impl ReplayClient {
         };
         tcp_io::serialize_into(stream, &request).unwrap();
     }
-    pub fn insert(&mut self, batch: Vec<Insertion>) {
-        let request = ReplayRequest::InsertBatch { batch };
+    pub fn set_alpha(&mut self, alpha: f64) {
+        let request = ReplayRequest::SetAlpha { alpha };
         let stream = match TcpStream::connect(self.server_addr) {
             Ok(stream) => stream,
             Err(e) => {
@@ -39,6 +43,28 @@ impl ReplayClient {
         };
         tcp_io::serialize_into(stream, &request).unwrap();
     }
+    pub fn insert(&mut self, batch: Vec<Insertion>) {
+        let request = ReplayRequest::InsertBatch { batch };
+        loop {
+            let stream = match TcpStream::connect(self.server_addr) {
+                Ok(stream) => stream,
+                Err(e) => {
+                    panic!("Could not connect to replay server: {}", e);
+                }
+            };
+            tcp_io::serialize_into(&stream, &request).unwrap();
+            let result: InsertBatchResult = tcp_io::deserialize_from(stream).unwrap();
+            match result {
+                Ok(()) => return,
+                Err(InsertBatchErrorKind::RateLimited) => {
+                    // The learners have fallen behind, so the insertion is
+                    // retried until the server can accept it again. The
+                    // delay keeps the retries from exhausting ephemeral ports
+                    std::thread::sleep(std::time::Duration::from_millis(100));
+                }
+            }
+        }
+    }
     pub fn sample_batch(&self, batch_len: usize) -> SampleBatchResult {
         let request = ReplayRequest::SampleBatch { batch_len };
         let stream = match TcpStream::connect(self.server_addr) {
@@ -50,4 +76,48 @@ impl ReplayClient {
         tcp_io::serialize_into(&stream, &request).unwrap();
         tcp_io::deserialize_from(stream).unwrap()
     }
+    pub fn stats(&self) -> ReplayStats {
+        let request = ReplayRequest::Stats;
+        let stream = match TcpStream::connect(self.server_addr) {
+            Ok(stream) => stream,
+            Err(e) => {
+                panic!("Could not connect to replay server: {}", e);
+            }
+        };
+        tcp_io::serialize_into(&stream, &request).unwrap();
+        tcp_io::deserialize_from(stream).unwrap()
+    }
+    pub fn len(&self) -> usize {
+        let request = ReplayRequest::Len;
+        let stream = match TcpStream::connect(self.server_addr) {
+            Ok(stream) => stream,
+            Err(e) => {
+                panic!("Could not connect to replay server: {}", e);
+            }
+        };
+        tcp_io::serialize_into(&stream, &request).unwrap();
+        tcp_io::deserialize_from(stream).unwrap()
+    }
+    pub fn fetch(&self, filter: TransitionFilter) -> FetchTransitionsReply {
+        let request = ReplayRequest::FetchTransitions { filter };
+        let stream = match TcpStream::connect(self.server_addr) {
+            Ok(stream) => stream,
+            Err(e) => {
+                panic!("Could not connect to replay server: {}", e);
+            }
+        };
+        tcp_io::serialize_into(&stream, &request).unwrap();
+        tcp_io::deserialize_from(stream).unwrap()
+    }
+    pub fn delete(&mut self, filter: TransitionFilter) -> usize {
+        let request = ReplayRequest::DeleteTransitions { filter };
+        let stream = match TcpStream::connect(self.server_addr) {
+            Ok(stream) => stream,
+            Err(e) => {
+                panic!("Could not connect to replay server: {}", e);
+            }
+        };
+        tcp_io::serialize_into(&stream, &request).unwrap();
+        tcp_io::deserialize_from(stream).unwrap()
+    }
 }
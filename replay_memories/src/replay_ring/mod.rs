@@ -1,32 +1,100 @@
-mod priority_circ_buffer;
+// also backs ReplaySequence
+pub(crate) mod priority_circ_buffer;
 
+use super::dataset_export;
+use super::initial_priority::{InitialPriority, InitialPriorityTracker};
+use super::traits::{
+    fallback_priority, priority_from_abs_td_error, PrioritizedMemory, ReplayMemory, SampleError,
+    SampledBatch,
+};
+use packets::{AgeBucket, ReplayStats};
 use priority_circ_buffer::PriorityCircBuffer;
-use replay_data::CompressedTransition;
+use priority_tree::Prioritization;
+use replay_data::{CompressedTransition, TransitionFilter, TransitionMetadata};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+struct RingEntry {
+    transition: CompressedTransition,
+    metadata: Option<TransitionMetadata>,
+}
 
 pub struct ReplayRing {
-    transitions: PriorityCircBuffer<f64, CompressedTransition>,
+    transitions: PriorityCircBuffer<f64, RingEntry>,
+    initial_priority: InitialPriorityTracker,
 }
 
 impl ReplayRing {
-    pub fn add_transition_with_priority(
+    // the upper bounds of the age buckets in ReplayStats
+    const AGE_BUCKET_MAX_AGES_SECS: [u64; 4] = [60, 10 * 60, 60 * 60, 24 * 60 * 60];
+
+    // the supplied priority is only used under InitialPriority::Supplied
+    pub fn add_transition(
         &mut self,
         transition: CompressedTransition,
-        priority: f64,
+        supplied_priority: Option<f64>,
+        metadata: Option<TransitionMetadata>,
     ) {
-        self.transitions.push(priority, transition);
+        let priority = self
+            .initial_priority
+            .initial_priority(self.transitions.max_priority(), supplied_priority);
+        self.initial_priority.observe(priority);
+        let entry = RingEntry {
+            transition,
+            metadata,
+        };
+        self.transitions.push(priority, entry);
     }
-    pub fn with_max_size(max_size: usize) -> Self {
+    fn from_transitions(transitions: PriorityCircBuffer<f64, RingEntry>) -> Self {
         Self {
-            transitions: PriorityCircBuffer::with_max_size(max_size),
+            transitions,
+            initial_priority: InitialPriorityTracker::new(InitialPriority::Supplied),
         }
     }
+    pub fn with_max_size(max_size: usize) -> Self {
+        Self::from_transitions(PriorityCircBuffer::with_max_size(max_size))
+    }
+    pub fn with_prioritization(
+        max_size: usize,
+        prioritization: Prioritization,
+        alpha: f64,
+    ) -> Self {
+        Self::from_transitions(PriorityCircBuffer::with_prioritization(
+            max_size,
+            prioritization,
+            alpha,
+        ))
+    }
+    pub fn initial_priority_policy(&self) -> InitialPriority {
+        self.initial_priority.policy()
+    }
+    pub fn set_initial_priority_policy(&mut self, policy: InitialPriority) {
+        self.initial_priority.set_policy(policy);
+    }
+    pub fn alpha(&self) -> f64 {
+        self.transitions.exponent()
+    }
+    // the priorities are kept as they were inserted, so changing alpha
+    // applies to every transition, old and new
+    pub fn set_alpha(&mut self, alpha: f64) {
+        self.transitions.set_exponent(alpha);
+    }
     pub fn truncate(&mut self, truncated_len: usize) {
         self.transitions.truncate(truncated_len);
     }
+    pub fn resize(&mut self, max_size: usize) {
+        self.transitions.resize(max_size);
+    }
     pub fn update_priorities(&mut self, indices: &[usize], priorities: &[f64]) {
-        for (index, priority) in indices.iter().zip(priorities.iter()) {
-            self.transitions.update_priority(*index, *priority);
+        let priorities: Vec<_> = indices
+            .iter()
+            .copied()
+            .zip(priorities.iter().copied())
+            .collect();
+        for (_index, priority) in &priorities {
+            self.initial_priority.observe(*priority);
         }
+        self.transitions.update_priorities(&priorities);
     }
     pub fn sample_batch(
         &self,
@@ -39,22 +107,151 @@ impl ReplayRing {
         for k in 0..batch_size {
             let range_start = (k as f64) / (batch_size as f64);
             let range_end = range_start + 1.0 / (batch_size as f64);
-            let (index, priority, transition) =
+            let (index, priority, entry) =
                 self.transitions
                     .sample_from_range(range_start, range_end, &mut rand::thread_rng());
             let probability = priority / total_priority;
             batch_indices.push(index);
             batch_probabilities.push(probability);
-            batch_transitions.push(transition);
+            batch_transitions.push(&entry.transition);
         }
         (batch_indices, batch_probabilities, batch_transitions)
     }
     pub fn min_probability(&self) -> f64 {
-        const EPSILON: f64 = 0.001;
-        let min_priority = self.transitions.min_priority().unwrap_or(EPSILON);
+        let min_priority = self
+            .transitions
+            .min_priority()
+            .unwrap_or_else(fallback_priority);
         min_priority / self.transitions.total_priority()
     }
     pub fn len(&self) -> usize {
         self.transitions.len()
     }
+    pub fn export_dataset<P: AsRef<Path>>(&self, path: P, shard_len: usize) {
+        let transitions = self
+            .transitions
+            .iter()
+            .map(|(_index, priority, entry)| (priority, &entry.transition))
+            // deleted transitions leave gaps in the buffer, so its length
+            // is only known once they are skipped
+            .collect::<Vec<_>>();
+        dataset_export::export_dataset(path, transitions.into_iter(), shard_len);
+    }
+    fn entries_matching(
+        &self,
+        filter: TransitionFilter,
+    ) -> impl Iterator<Item = (usize, &CompressedTransition, &TransitionMetadata)> {
+        self.transitions
+            .iter()
+            .filter_map(move |(index, _priority, entry)| {
+                let metadata = entry.metadata.as_ref()?;
+                filter
+                    .matches(metadata)
+                    .then_some((index, &entry.transition, metadata))
+            })
+    }
+    // the transitions matching the filter from oldest to newest. Transitions
+    // without metadata never match
+    pub fn fetch(
+        &self,
+        filter: TransitionFilter,
+    ) -> Vec<(&CompressedTransition, &TransitionMetadata)> {
+        self.entries_matching(filter)
+            .map(|(_index, transition, metadata)| (transition, metadata))
+            .collect()
+    }
+    // returns the number of deleted transitions
+    pub fn delete(&mut self, filter: TransitionFilter) -> usize {
+        let indices: Vec<_> = self
+            .entries_matching(filter)
+            .map(|(index, _transition, _metadata)| index)
+            .collect();
+        for index in &indices {
+            self.transitions.remove(*index);
+        }
+        indices.len()
+    }
+    pub fn stats(&self) -> ReplayStats {
+        let now = TransitionMetadata::current_timestamp();
+        let mut without_metadata_count = 0;
+        let mut per_actor_counts = BTreeMap::new();
+        let max_ages = Self::AGE_BUCKET_MAX_AGES_SECS.map(Some);
+        let mut age_buckets: Vec<_> = max_ages
+            .into_iter()
+            .chain([None])
+            .map(|max_age_secs| AgeBucket {
+                max_age_secs,
+                transition_count: 0,
+            })
+            .collect();
+        for (_index, _priority, entry) in self.transitions.iter() {
+            let Some(ref metadata) = entry.metadata else {
+                without_metadata_count += 1;
+                continue;
+            };
+            *per_actor_counts.entry(metadata.actor_id).or_insert(0) += 1;
+            let age_secs = now.saturating_sub(metadata.timestamp) / 1000;
+            let bucket = age_buckets
+                .iter_mut()
+                .find(|bucket| bucket.max_age_secs.is_none_or(|max_age| age_secs < max_age))
+                .unwrap();
+            bucket.transition_count += 1;
+        }
+        ReplayStats {
+            transition_count: self.len(),
+            without_metadata_count,
+            per_actor_counts,
+            age_buckets,
+        }
+    }
+}
+
+impl ReplayMemory for ReplayRing {
+    type Transition = CompressedTransition;
+    type Sampled<'a> = &'a CompressedTransition;
+
+    fn len(&self) -> usize {
+        self.transitions.len()
+    }
+    fn insert(&mut self, transition: CompressedTransition, metadata: Option<TransitionMetadata>) {
+        self.add_transition(transition, None, metadata);
+    }
+    fn sample(
+        &self,
+        batch_size: usize,
+    ) -> Result<SampledBatch<&CompressedTransition>, SampleError> {
+        if self.len() < batch_size {
+            return Err(SampleError::NotEnoughTransitions);
+        }
+        let (indices, probabilities, transitions) = self.sample_batch(batch_size);
+        Ok(SampledBatch {
+            indices,
+            probabilities,
+            transitions,
+            min_probability: self.min_probability(),
+            memory_len: self.len(),
+        })
+    }
+}
+
+impl PrioritizedMemory for ReplayRing {
+    fn insert_with_abs_td_error(
+        &mut self,
+        transition: CompressedTransition,
+        abs_td_error: f64,
+        metadata: Option<TransitionMetadata>,
+    ) {
+        let priority = priority_from_abs_td_error(abs_td_error);
+        self.add_transition(transition, Some(priority), metadata);
+    }
+    fn update_priorities_with_td_errors(&mut self, indices: &[usize], abs_td_errors: &[f64]) {
+        let priorities: Vec<_> = abs_td_errors
+            .iter()
+            .map(|abs_td_error| priority_from_abs_td_error(*abs_td_error))
+            .collect();
+        self.update_priorities(indices, &priorities);
+    }
+    fn set_alpha(&mut self, alpha: f64) {
+        ReplayRing::set_alpha(self, alpha);
+    }
 }
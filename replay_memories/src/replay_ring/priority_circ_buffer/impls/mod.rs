@@ -1,39 +1,103 @@
 mod sampling;
 
 use super::PriorityCircBuffer;
-use priority_tree::{Priority, PriorityTree};
+use priority_tree::{Priorities, Prioritization, Priority};
 
 impl<P: Priority, V> PriorityCircBuffer<P, V> {
     pub fn with_max_size(max_size: usize) -> Self {
+        Self::with_prioritization(max_size, Prioritization::Proportional, 1.0)
+    }
+
+    pub fn with_prioritization(
+        max_size: usize,
+        prioritization: Prioritization,
+        exponent: f64,
+    ) -> Self {
+        Self::check_max_size(max_size);
         Self {
-            priorities: PriorityTree::with_leaf_count(max_size),
+            priorities: Priorities::with_leaf_count(prioritization, exponent, max_size),
             values: Self::vec_of_nones(max_size),
             max_size,
             head: 0,
             tail: 0,
+            removed_len: 0,
         }
     }
 
+    // one slot always stays empty, so that a full buffer can be told apart
+    // from an empty one, which leaves no room for any value below 2
+    fn check_max_size(max_size: usize) {
+        assert!(
+            max_size >= 2,
+            "PriorityCircBuffer needs a max size of at least 2"
+        );
+    }
+
     fn vec_of_nones<T>(len: usize) -> Vec<Option<T>> {
         std::iter::repeat_with(|| None).take(len).collect()
     }
 
+    // keeps the newest truncated_len values, dropping the gaps older than them
     pub fn truncate(&mut self, truncated_len: usize) {
-        if self.len() > truncated_len {
-            let new_tail = self.mod_max_size(self.head as isize - truncated_len as isize);
-            while self.tail != new_tail {
-                self.reset_entry(self.tail);
-                self.tail += 1;
-                if self.tail == self.max_size {
-                    self.tail = 0;
-                }
+        // walks back from head until truncated_len values have been passed
+        let mut new_tail = self.head;
+        let mut kept_len = 0;
+        while kept_len < truncated_len && new_tail != self.tail {
+            new_tail = self.mod_max_size(new_tail as isize - 1);
+            if self.values[new_tail].is_some() {
+                kept_len += 1;
+            }
+        }
+        let mut truncated_indices = vec![];
+        while self.tail != new_tail {
+            if self.values[self.tail].take().is_none() {
+                self.removed_len -= 1;
+            }
+            truncated_indices.push(self.tail);
+            self.tail += 1;
+            if self.tail == self.max_size {
+                self.tail = 0;
             }
         }
+        self.priorities.reset_many(&truncated_indices);
     }
 
-    fn reset_entry(&mut self, index: usize) {
-        self.priorities.reset(index);
-        self.values[index] = None;
+    // changes the max size, keeping the newest values that fit
+    pub fn resize(&mut self, max_size: usize) {
+        if max_size == self.max_size {
+            return;
+        }
+        Self::check_max_size(max_size);
+        let len = self.len();
+        // the slot that always stays empty can't be kept either
+        let kept_len = len.min(max_size - 1);
+        let kept_indices: Vec<_> = self.indices().skip(len - kept_len).collect();
+        // the kept values are laid out without the gaps left by removed ones
+        let mut values = Self::vec_of_nones(max_size);
+        let mut priorities = Vec::with_capacity(kept_len);
+        for (new_index, index) in kept_indices.into_iter().enumerate() {
+            values[new_index] = self.values[index].take();
+            priorities.push(self.priorities.raw_priority(index));
+        }
+        self.priorities = Priorities::with_priorities(
+            self.priorities.prioritization(),
+            self.priorities.exponent(),
+            max_size,
+            priorities,
+        );
+        self.values = values;
+        self.max_size = max_size;
+        self.head = kept_len;
+        self.tail = 0;
+        self.removed_len = 0;
+    }
+
+    // leaves a gap that is never sampled, until the buffer wraps around to it
+    pub fn remove(&mut self, index: usize) {
+        if self.values[index].take().is_some() {
+            self.priorities.reset(index);
+            self.removed_len += 1;
+        }
     }
 
     pub fn push(&mut self, priority: P, value: V) {
@@ -49,12 +113,34 @@ impl<P: Priority, V> PriorityCircBuffer<P, V> {
         )
     }
 
+    // drops the oldest value first if the buffer is full
+    pub fn push_evicting(&mut self, priority: P, value: V) {
+        if self.span_len() == self.max_size - 1 {
+            // drops the oldest value along with the gaps before it, or only
+            // the gaps if every value was removed
+            self.truncate(self.len().saturating_sub(1));
+        }
+        self.push(priority, value);
+    }
+
+    pub fn exponent(&self) -> f64 {
+        self.priorities.exponent()
+    }
+
+    // changes the exponent of the prioritization, which takes time linear in
+    // the max size
+    pub fn set_exponent(&mut self, exponent: f64) {
+        self.priorities.set_exponent(exponent);
+    }
+
     pub fn min_priority(&self) -> Option<P> {
         self.priorities.min()
     }
 
+    // refers to the priorities as they were passed to push or update_priority,
+    // as opposed to the priorities that sampling follows
     pub fn max_priority(&self) -> Option<P> {
-        self.priorities.max()
+        self.priorities.max_raw_priority()
     }
 
     pub fn total_priority(&self) -> P {
@@ -65,10 +151,35 @@ impl<P: Priority, V> PriorityCircBuffer<P, V> {
         self.priorities.update(index, priority);
     }
 
+    pub fn update_priorities(&mut self, priorities: &[(usize, P)]) {
+        self.priorities.update_many(priorities);
+    }
+
     pub fn len(&self) -> usize {
+        self.span_len() - self.removed_len
+    }
+
+    // the number of slots between tail and head, including removed ones
+    fn span_len(&self) -> usize {
         self.mod_max_size(self.head as isize - self.tail as isize)
     }
 
+    // the indices of the stored values from oldest to newest
+    fn indices(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.span_len())
+            .map(move |offset| (self.tail + offset) % self.max_size)
+            .filter(move |index| self.values[*index].is_some())
+    }
+
+    // iterates over the stored values from oldest to newest, along with their
+    // priorities as they were passed to push or update_priority
+    pub fn iter(&self) -> impl Iterator<Item = (usize, P, &V)> {
+        self.indices().map(move |index| {
+            let value = self.values[index].as_ref().unwrap();
+            (index, self.priorities.raw_priority(index), value)
+        })
+    }
+
     fn mod_max_size(&self, n: isize) -> usize {
         n.rem_euclid(self.max_size as isize) as usize
     }
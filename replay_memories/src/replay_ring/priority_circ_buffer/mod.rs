@@ -1,11 +1,13 @@
 mod impls;
 
-use priority_tree::{Priority, PriorityTree};
+use priority_tree::{Priorities, Priority};
 
 pub struct PriorityCircBuffer<P: Priority, V> {
-    priorities: PriorityTree<P>,
+    priorities: Priorities<P>,
     values: Vec<Option<V>>,
     max_size: usize,
     head: usize,
     tail: usize,
+    // the number of values between tail and head that were removed
+    removed_len: usize,
 }
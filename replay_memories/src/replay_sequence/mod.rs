@@ -0,0 +1,230 @@
+mod sequence_builder;
+
+use super::initial_priority::{InitialPriority, InitialPriorityTracker};
+use super::replay_ring::priority_circ_buffer::PriorityCircBuffer;
+use super::traits::{
+    fallback_priority, priority_from_abs_td_error, PrioritizedMemory, ReplayMemory, SampleError,
+    SampledBatch,
+};
+use priority_tree::Prioritization;
+use replay_data::{CompressedTransition, TransitionMetadata};
+pub use sequence_builder::SequenceBuilder;
+
+// Consecutive transitions of one episode. The recurrent state is only
+// unrolled over the first burn_in_len of them, and the rest are trained on
+#[derive(Clone)]
+pub struct TransitionSequence {
+    pub transitions: Vec<CompressedTransition>,
+    pub burn_in_len: usize,
+}
+
+impl TransitionSequence {
+    pub fn burn_in(&self) -> &[CompressedTransition] {
+        &self.transitions[..self.burn_in_len]
+    }
+    pub fn trained(&self) -> &[CompressedTransition] {
+        &self.transitions[self.burn_in_len..]
+    }
+}
+
+// A prioritized memory of transition sequences, for recurrent agents. Each
+// sequence has a single priority, which mixes the max and the mean of the td
+// errors of its trained steps as in R2D2. When full, the oldest sequences are
+// dropped
+pub struct ReplaySequence {
+    sequences: PriorityCircBuffer<f64, TransitionSequence>,
+    initial_priority: InitialPriorityTracker,
+    // the weight of the max in the mix, where the rest goes to the mean
+    priority_eta: f64,
+}
+
+impl ReplaySequence {
+    const DEFAULT_PRIORITY_ETA: f64 = 0.9;
+
+    fn from_sequences(sequences: PriorityCircBuffer<f64, TransitionSequence>) -> Self {
+        Self {
+            sequences,
+            initial_priority: InitialPriorityTracker::new(InitialPriority::CurrentMax),
+            priority_eta: Self::DEFAULT_PRIORITY_ETA,
+        }
+    }
+    pub fn with_max_size(max_size: usize) -> Self {
+        Self::from_sequences(PriorityCircBuffer::with_max_size(max_size))
+    }
+    pub fn with_prioritization(
+        max_size: usize,
+        prioritization: Prioritization,
+        alpha: f64,
+    ) -> Self {
+        Self::from_sequences(PriorityCircBuffer::with_prioritization(
+            max_size,
+            prioritization,
+            alpha,
+        ))
+    }
+    pub fn priority_eta(&self) -> f64 {
+        self.priority_eta
+    }
+    pub fn set_priority_eta(&mut self, priority_eta: f64) {
+        self.priority_eta = priority_eta;
+    }
+    // reduces the |td errors| of the trained steps of a sequence to the one
+    // that its priority follows
+    pub fn sequence_abs_td_error(&self, step_abs_td_errors: &[f64]) -> f64 {
+        if step_abs_td_errors.is_empty() {
+            return 0.0;
+        }
+        let max = step_abs_td_errors.iter().copied().fold(0.0, f64::max);
+        let mean = step_abs_td_errors.iter().sum::<f64>() / step_abs_td_errors.len() as f64;
+        self.priority_eta * max + (1.0 - self.priority_eta) * mean
+    }
+    pub fn initial_priority_policy(&self) -> InitialPriority {
+        self.initial_priority.policy()
+    }
+    pub fn set_initial_priority_policy(&mut self, policy: InitialPriority) {
+        self.initial_priority.set_policy(policy);
+    }
+    pub fn alpha(&self) -> f64 {
+        self.sequences.exponent()
+    }
+    pub fn set_alpha(&mut self, alpha: f64) {
+        self.sequences.set_exponent(alpha);
+    }
+    // the supplied priority is only used under InitialPriority::Supplied
+    pub fn add_sequence(&mut self, sequence: TransitionSequence, supplied_priority: Option<f64>) {
+        let priority = self
+            .initial_priority
+            .initial_priority(self.sequences.max_priority(), supplied_priority);
+        self.initial_priority.observe(priority);
+        self.sequences.push_evicting(priority, sequence);
+    }
+    pub fn truncate(&mut self, truncated_len: usize) {
+        self.sequences.truncate(truncated_len);
+    }
+    pub fn update_priorities(&mut self, indices: &[usize], priorities: &[f64]) {
+        let priorities: Vec<_> = indices
+            .iter()
+            .copied()
+            .zip(priorities.iter().copied())
+            .collect();
+        for (_index, priority) in &priorities {
+            self.initial_priority.observe(*priority);
+        }
+        self.sequences.update_priorities(&priorities);
+    }
+    // takes the |td errors| of the trained steps of every sampled sequence
+    pub fn update_priorities_with_step_td_errors(
+        &mut self,
+        indices: &[usize],
+        step_abs_td_errors: &[Vec<f64>],
+    ) {
+        let priorities: Vec<_> = step_abs_td_errors
+            .iter()
+            .map(|abs_td_errors| {
+                priority_from_abs_td_error(self.sequence_abs_td_error(abs_td_errors))
+            })
+            .collect();
+        self.update_priorities(indices, &priorities);
+    }
+    pub fn sample_batch(
+        &self,
+        batch_size: usize,
+    ) -> (Vec<usize>, Vec<f64>, Vec<&TransitionSequence>) {
+        let mut batch_indices = vec![];
+        let mut batch_probabilities = vec![];
+        let mut batch_sequences = vec![];
+        let total_priority = self.sequences.total_priority();
+        for k in 0..batch_size {
+            let range_start = (k as f64) / (batch_size as f64);
+            let range_end = range_start + 1.0 / (batch_size as f64);
+            let (index, priority, sequence) =
+                self.sequences
+                    .sample_from_range(range_start, range_end, &mut rand::thread_rng());
+            let probability = priority / total_priority;
+            batch_indices.push(index);
+            batch_probabilities.push(probability);
+            batch_sequences.push(sequence);
+        }
+        (batch_indices, batch_probabilities, batch_sequences)
+    }
+    pub fn min_probability(&self) -> f64 {
+        let min_priority = self
+            .sequences
+            .min_priority()
+            .unwrap_or_else(fallback_priority);
+        min_priority / self.sequences.total_priority()
+    }
+    pub fn len(&self) -> usize {
+        self.sequences.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl ReplayMemory for ReplaySequence {
+    type Transition = TransitionSequence;
+    type Sampled<'a> = &'a TransitionSequence;
+
+    fn len(&self) -> usize {
+        self.sequences.len()
+    }
+    fn insert(&mut self, sequence: TransitionSequence, _metadata: Option<TransitionMetadata>) {
+        self.add_sequence(sequence, None);
+    }
+    fn sample(&self, batch_size: usize) -> Result<SampledBatch<&TransitionSequence>, SampleError> {
+        if self.len() < batch_size {
+            return Err(SampleError::NotEnoughTransitions);
+        }
+        let (indices, probabilities, transitions) = self.sample_batch(batch_size);
+        Ok(SampledBatch {
+            indices,
+            probabilities,
+            transitions,
+            min_probability: self.min_probability(),
+            memory_len: self.len(),
+        })
+    }
+}
+
+// the |td errors| here are those of whole sequences, as reduced by
+// sequence_abs_td_error
+impl PrioritizedMemory for ReplaySequence {
+    fn insert_with_abs_td_error(
+        &mut self,
+        sequence: TransitionSequence,
+        abs_td_error: f64,
+        _metadata: Option<TransitionMetadata>,
+    ) {
+        let priority = priority_from_abs_td_error(abs_td_error);
+        self.add_sequence(sequence, Some(priority));
+    }
+    fn update_priorities_with_td_errors(&mut self, indices: &[usize], abs_td_errors: &[f64]) {
+        let priorities: Vec<_> = abs_td_errors
+            .iter()
+            .map(|abs_td_error| priority_from_abs_td_error(*abs_td_error))
+            .collect();
+        self.update_priorities(indices, &priorities);
+    }
+    fn set_alpha(&mut self, alpha: f64) {
+        ReplaySequence::set_alpha(self, alpha);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mixes_max_and_mean_of_td_errors() {
+        let mut memory = ReplaySequence::with_max_size(4);
+        // max 6, mean 3
+        let abs_td_errors = [1.0, 2.0, 6.0];
+        assert!((memory.sequence_abs_td_error(&abs_td_errors) - 5.7).abs() < 1e-12);
+        memory.set_priority_eta(0.5);
+        assert!((memory.sequence_abs_td_error(&abs_td_errors) - 4.5).abs() < 1e-12);
+        memory.set_priority_eta(0.0);
+        assert!((memory.sequence_abs_td_error(&abs_td_errors) - 3.0).abs() < 1e-12);
+        assert_eq!(memory.sequence_abs_td_error(&[]), 0.0);
+    }
+}
@@ -0,0 +1,214 @@
+use super::TransitionSequence;
+use replay_data::CompressedTransition;
+use std::collections::VecDeque;
+
+// Cuts the transitions of a single env into overlapping sequences. Every
+// sequence_len - overlap_len steps, the last sequence_len transitions are
+// emitted as a sequence. The end of an episode emits whatever steps haven't
+// been emitted yet, so the last sequence of an episode may be shorter, and
+// sequences never span episodes
+//
+// The burn-in of a sequence is made of the steps it shares with the previous
+// one, so the overlap must be at least as long as the burn-in
+pub struct SequenceBuilder {
+    sequence_len: usize,
+    burn_in_len: usize,
+    period: usize,
+    window: VecDeque<CompressedTransition>,
+    // the number of transitions in the window that no sequence has been
+    // emitted with yet
+    unemitted_len: usize,
+}
+
+impl SequenceBuilder {
+    pub fn new(sequence_len: usize, burn_in_len: usize, overlap_len: usize) -> Self {
+        assert!(
+            burn_in_len < sequence_len,
+            "the burn-in must leave steps to train on"
+        );
+        assert!(
+            overlap_len < sequence_len,
+            "consecutive sequences must not overlap entirely"
+        );
+        assert!(
+            overlap_len >= burn_in_len,
+            "the overlap must cover the burn-in"
+        );
+        Self {
+            sequence_len,
+            burn_in_len,
+            period: sequence_len - overlap_len,
+            window: VecDeque::with_capacity(sequence_len),
+            unemitted_len: 0,
+        }
+    }
+    pub fn push(&mut self, transition: CompressedTransition) -> Option<TransitionSequence> {
+        let terminated = transition.terminated;
+        if self.window.len() == self.sequence_len {
+            self.window.pop_front();
+        }
+        self.window.push_back(transition);
+        self.unemitted_len += 1;
+        let window_full = self.window.len() == self.sequence_len;
+        let sequence = if terminated || (window_full && self.unemitted_len >= self.period) {
+            Some(self.emit())
+        } else {
+            None
+        };
+        if terminated {
+            self.window.clear();
+            self.unemitted_len = 0;
+        }
+        sequence
+    }
+    // ends the current episode without a terminal transition, as happens on
+    // truncation. Returns its remaining steps, if there are any
+    pub fn flush(&mut self) -> Option<TransitionSequence> {
+        let sequence = (self.unemitted_len > 0).then(|| self.emit());
+        self.window.clear();
+        self.unemitted_len = 0;
+        sequence
+    }
+    fn emit(&mut self) -> TransitionSequence {
+        // steps that were already emitted only serve as burn-in, and the
+        // first steps of an episode need none, since the recurrent state
+        // starts out fresh there as well. Otherwise, the overlap is at least
+        // burn_in_len long, so the burn-in is never cut short
+        let emitted_len = self.window.len() - self.unemitted_len;
+        self.unemitted_len = 0;
+        TransitionSequence {
+            transitions: self.window.iter().cloned().collect(),
+            burn_in_len: emitted_len.min(self.burn_in_len),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageOwned, ImageOwned2};
+    use replay_data::CompressedImageOwned2;
+
+    enum EpisodeEnd {
+        Terminated,
+        Truncated,
+    }
+
+    // the reward of a transition is its step, counted across episodes
+    fn transition(step: usize, terminated: bool) -> CompressedTransition {
+        let frame = || CompressedImageOwned2::from(&ImageOwned2::zeroed(2, 2));
+        CompressedTransition {
+            state: [frame()].into(),
+            next_state: [frame()].into(),
+            action: 0,
+            reward: step as f64,
+            terminated,
+        }
+    }
+
+    fn steps(transitions: &[CompressedTransition]) -> Vec<usize> {
+        transitions
+            .iter()
+            .map(|transition| transition.reward as usize)
+            .collect()
+    }
+
+    // returns the emitted sequences along with the episode of every step
+    fn build(
+        builder: &mut SequenceBuilder,
+        episodes: &[(usize, EpisodeEnd)],
+    ) -> (Vec<TransitionSequence>, Vec<usize>) {
+        let mut sequences = vec![];
+        let mut step_episodes = vec![];
+        for (episode, (episode_len, end)) in episodes.iter().enumerate() {
+            for episode_step in 0..*episode_len {
+                let terminated =
+                    matches!(end, EpisodeEnd::Terminated) && episode_step + 1 == *episode_len;
+                sequences.extend(builder.push(transition(step_episodes.len(), terminated)));
+                step_episodes.push(episode);
+            }
+            if matches!(end, EpisodeEnd::Truncated) {
+                sequences.extend(builder.flush());
+            }
+        }
+        (sequences, step_episodes)
+    }
+
+    #[test]
+    fn emits_overlapping_sequences() {
+        let mut builder = SequenceBuilder::new(5, 2, 3);
+        let (sequences, _) = build(&mut builder, &[(12, EpisodeEnd::Truncated)]);
+        let emitted: Vec<_> = sequences
+            .iter()
+            .map(|sequence| (steps(&sequence.transitions), sequence.burn_in_len))
+            .collect();
+        assert_eq!(
+            emitted,
+            [
+                (vec![0, 1, 2, 3, 4], 0),
+                (vec![2, 3, 4, 5, 6], 2),
+                (vec![4, 5, 6, 7, 8], 2),
+                (vec![6, 7, 8, 9, 10], 2),
+                // the end of the episode emits the step left over
+                (vec![7, 8, 9, 10, 11], 2),
+            ]
+        );
+        assert!(builder.flush().is_none());
+    }
+
+    #[test]
+    fn sequences_cover_every_step_within_episodes() {
+        let episodes = [
+            (12, EpisodeEnd::Terminated),
+            (1, EpisodeEnd::Terminated),
+            (3, EpisodeEnd::Truncated),
+            (7, EpisodeEnd::Terminated),
+            (20, EpisodeEnd::Truncated),
+            (5, EpisodeEnd::Terminated),
+        ];
+        for (sequence_len, burn_in_len, overlap_len) in
+            [(5, 2, 3), (4, 0, 1), (6, 3, 3), (3, 1, 2), (8, 2, 5)]
+        {
+            let mut builder = SequenceBuilder::new(sequence_len, burn_in_len, overlap_len);
+            let (sequences, step_episodes) = build(&mut builder, &episodes);
+            let episode_lens: Vec<_> = episodes.iter().map(|(len, _)| *len).collect();
+            let mut trained = vec![false; step_episodes.len()];
+            for sequence in &sequences {
+                let sequence_steps = steps(&sequence.transitions);
+                let first = sequence_steps[0];
+                let last = *sequence_steps.last().unwrap();
+                assert!(sequence_steps.windows(2).all(|pair| pair[1] == pair[0] + 1));
+                let episode = step_episodes[first];
+                assert_eq!(
+                    step_episodes[last], episode,
+                    "sequences never span episodes"
+                );
+
+                let episode_start = step_episodes.iter().position(|e| *e == episode).unwrap();
+                let ends_episode = step_episodes.get(last + 1) != Some(&episode);
+                let expected_len = if ends_episode {
+                    sequence_len.min(episode_lens[episode])
+                } else {
+                    sequence_len
+                };
+                assert_eq!(sequence_steps.len(), expected_len);
+                let expected_burn_in_len = if first == episode_start {
+                    0
+                } else {
+                    burn_in_len
+                };
+                assert_eq!(sequence.burn_in_len, expected_burn_in_len);
+
+                // the burn-in only replays steps that were already trained on
+                for step in steps(sequence.burn_in()) {
+                    assert!(trained[step]);
+                }
+                assert!(!sequence.trained().is_empty());
+                for step in steps(sequence.trained()) {
+                    trained[step] = true;
+                }
+            }
+            assert!(trained.iter().all(|trained| *trained));
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use super::traits::{PersistentMemory, PrioritizedMemory, ReplayMemory, SampleError, SampledBatch};
+use super::{InitialPriority, ReplayPrioritized};
+use file_io::LoadError;
+use replay_data::{CompressedArcTransition, TransitionMetadata};
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+// A handle to a ReplayPrioritized that can be cloned and sent to other
+// threads, so that sampling, insertion and saving don't have to happen on the
+// same one. Sampled transitions are returned by value, which only clones the
+// pointers to their frames. This way the memory is only locked while sampling,
+// and not while the batch is trained on
+#[derive(Clone)]
+pub struct ReplayShared {
+    memory: Arc<Mutex<ReplayPrioritized>>,
+}
+
+impl ReplayShared {
+    pub fn new(memory: ReplayPrioritized) -> Self {
+        Self {
+            memory: Arc::new(Mutex::new(memory)),
+        }
+    }
+    // a panic on another thread while it held the lock doesn't leave the
+    // memory in an inconsistent state, so the poison is ignored
+    fn lock(&self) -> MutexGuard<'_, ReplayPrioritized> {
+        self.memory
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+    pub fn add_transition(&self, transition: CompressedArcTransition) {
+        self.lock().add_transition(transition);
+    }
+    pub fn update_priorities_with_td_errors(&self, indices: &[usize], abs_td_errors: &[f64]) {
+        self.lock()
+            .update_priorities_with_td_errors(indices, abs_td_errors);
+    }
+    pub fn set_alpha(&self, alpha: f64) {
+        self.lock().set_alpha(alpha);
+    }
+    pub fn set_initial_priority_policy(&self, policy: InitialPriority) {
+        self.lock().set_initial_priority_policy(policy);
+    }
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    // only copying the memory happens under the lock, so that it can keep
+    // being sampled from and inserted into while the copy is written
+    pub fn save<P: AsRef<Path>>(&self, path: P) {
+        let snapshot = self.lock().snapshot();
+        snapshot.save(path);
+        self.lock().saved_snapshot(&snapshot);
+    }
+    pub fn load<P: AsRef<Path>>(&self, path: P) -> Result<(), LoadError> {
+        self.lock().load(path)
+    }
+}
+
+impl ReplayMemory for ReplayShared {
+    type Transition = CompressedArcTransition;
+    type Sampled<'a> = CompressedArcTransition;
+
+    fn len(&self) -> usize {
+        ReplayShared::len(self)
+    }
+    fn insert(
+        &mut self,
+        transition: CompressedArcTransition,
+        _metadata: Option<TransitionMetadata>,
+    ) {
+        self.add_transition(transition);
+    }
+    fn sample(
+        &self,
+        batch_size: usize,
+    ) -> Result<SampledBatch<CompressedArcTransition>, SampleError> {
+        let memory = self.lock();
+        let batch = memory.sample(batch_size)?;
+        Ok(SampledBatch {
+            indices: batch.indices,
+            probabilities: batch.probabilities,
+            transitions: batch.transitions.into_iter().cloned().collect(),
+            min_probability: batch.min_probability,
+            memory_len: batch.memory_len,
+        })
+    }
+}
+
+impl PrioritizedMemory for ReplayShared {
+    fn insert_with_abs_td_error(
+        &mut self,
+        transition: CompressedArcTransition,
+        abs_td_error: f64,
+        metadata: Option<TransitionMetadata>,
+    ) {
+        self.lock()
+            .insert_with_abs_td_error(transition, abs_td_error, metadata);
+    }
+    fn update_priorities_with_td_errors(&mut self, indices: &[usize], abs_td_errors: &[f64]) {
+        ReplayShared::update_priorities_with_td_errors(self, indices, abs_td_errors);
+    }
+    fn set_alpha(&mut self, alpha: f64) {
+        ReplayShared::set_alpha(self, alpha);
+    }
+}
+
+impl PersistentMemory for ReplayShared {
+    fn save<P: AsRef<Path>>(&self, path: P) {
+        ReplayShared::save(self, path);
+    }
+    fn load<P: AsRef<Path>>(&mut self, path: P) -> Result<(), LoadError> {
+        ReplayShared::load(self, path)
+    }
+}
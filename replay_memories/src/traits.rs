@@ -0,0 +1,87 @@
+use file_io::LoadError;
+use replay_data::TransitionMetadata;
+use std::borrow::Borrow;
+use std::path::Path;
+
+// Priorities are stored as |td error| + EPSILON, so that transitions whose td
+// error is zero can still be sampled
+const EPSILON: f64 = 0.001;
+
+pub(crate) fn priority_from_abs_td_error(abs_td_error: f64) -> f64 {
+    abs_td_error + EPSILON
+}
+
+// The priority assumed for transitions when there is no better estimate
+pub(crate) fn fallback_priority() -> f64 {
+    EPSILON
+}
+
+pub struct SampledBatch<S> {
+    pub indices: Vec<usize>,
+    pub probabilities: Vec<f64>,
+    pub transitions: Vec<S>,
+    // the minimal sampling probability of any transition in memory
+    pub min_probability: f64,
+    pub memory_len: usize,
+}
+
+#[derive(Debug)]
+pub enum SampleError {
+    NotEnoughTransitions,
+    RateLimited,
+}
+
+// The replay wrappers are generic over these traits, so switching a wrapper to
+// another memory only takes changing its memory type parameter, as long as the
+// new memory stores the same kind of transitions. The memory is still chosen at
+// compile time, not at runtime
+pub trait ReplayMemory {
+    type Transition;
+    // sampled transitions are either borrowed from the memory or, when the
+    // memory can't lend them, owned
+    type Sampled<'a>: Borrow<Self::Transition>
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    // memories that don't keep metadata discard it
+    fn insert(&mut self, transition: Self::Transition, metadata: Option<TransitionMetadata>);
+    // inserts the transitions together, which remote memories can do in a
+    // single request
+    fn insert_batch(&mut self, batch: Vec<(Self::Transition, Option<TransitionMetadata>)>) {
+        for (transition, metadata) in batch {
+            self.insert(transition, metadata);
+        }
+    }
+    fn sample(&self, batch_size: usize) -> Result<SampledBatch<Self::Sampled<'_>>, SampleError>;
+}
+
+pub trait PrioritizedMemory: ReplayMemory {
+    // the td error is only used if the memory's initial priority policy
+    // allows supplied priorities
+    fn insert_with_abs_td_error(
+        &mut self,
+        transition: Self::Transition,
+        abs_td_error: f64,
+        metadata: Option<TransitionMetadata>,
+    );
+    // see ReplayMemory::insert_batch
+    fn insert_batch_with_abs_td_errors(
+        &mut self,
+        batch: Vec<(Self::Transition, f64, Option<TransitionMetadata>)>,
+    ) {
+        for (transition, abs_td_error, metadata) in batch {
+            self.insert_with_abs_td_error(transition, abs_td_error, metadata);
+        }
+    }
+    fn update_priorities_with_td_errors(&mut self, indices: &[usize], abs_td_errors: &[f64]);
+    fn set_alpha(&mut self, alpha: f64);
+}
+
+pub trait PersistentMemory {
+    fn save<P: AsRef<Path>>(&self, path: P);
+    fn load<P: AsRef<Path>>(&mut self, path: P) -> Result<(), LoadError>;
+}
@@ -1,15 +1,17 @@
 mod transition_serializer;
 
-use file_io::{create_file_buf_write, has_data_left, open_file_buf_read};
-use replay_data::{CompressedImageOwned2, CompressedRcTransition, SavedTransition};
+use file_io::{create_file_buf_write, has_data_left, open_file_buf_read, LoadError};
+use replay_data::{
+    CompressedArcState, CompressedArcTransition, CompressedImageOwned2, SavedState, SavedTransition,
+};
 use std::path::Path;
-use std::rc::Rc;
+use std::sync::Arc;
 use transition_serializer::TransitionSerializer;
 
 pub fn save_transitions<'a, P, I>(path: P, transitions: I)
 where
     P: AsRef<Path>,
-    I: IntoIterator<Item = &'a CompressedRcTransition>,
+    I: IntoIterator<Item = &'a CompressedArcTransition>,
 {
     let path = path.as_ref();
     let (serialized_frames, serialized_transitions) = TransitionSerializer::new().run(transitions);
@@ -27,36 +29,61 @@ where
     }
 }
 
-pub fn load_transitions<P: AsRef<Path>>(path: P, max_size: usize) -> Vec<CompressedRcTransition> {
+fn load_state(
+    frames: &[Arc<CompressedImageOwned2>],
+    saved_state: &SavedState,
+) -> Result<CompressedArcState, LoadError> {
+    if let Some(frame_index) = saved_state
+        .frames()
+        .iter()
+        .find(|frame_index| **frame_index >= frames.len())
+    {
+        return Err(LoadError::Invalid(format!(
+            "frame index {} is out of range (only {} frames were saved)",
+            frame_index,
+            frames.len()
+        )));
+    }
+    let state: Vec<_> = saved_state
+        .frames()
+        .iter()
+        .map(|frame_index| Arc::clone(&frames[*frame_index]))
+        .collect();
+    Ok(state.into())
+}
+
+pub fn load_transitions<P: AsRef<Path>>(
+    path: P,
+    max_size: usize,
+) -> Result<Vec<CompressedArcTransition>, LoadError> {
     let path = path.as_ref();
-    let mut frames_file = open_file_buf_read(path.join("frames")).unwrap();
-    let mut frames: Vec<Rc<CompressedImageOwned2>> = vec![];
-    while has_data_left(&mut frames_file).unwrap() {
-        let frame = bincode::deserialize_from(&mut frames_file).unwrap();
-        let frame = Rc::new(frame);
+    let frames_path = path.join("frames");
+    let mut frames_file =
+        open_file_buf_read(&frames_path).map_err(|e| LoadError::Io(frames_path.clone(), e))?;
+    let mut frames: Vec<Arc<CompressedImageOwned2>> = vec![];
+    while has_data_left(&mut frames_file).map_err(|e| LoadError::Io(frames_path.clone(), e))? {
+        let frame = bincode::deserialize_from(&mut frames_file)
+            .map_err(|e| LoadError::Decode(frames_path.clone(), e))?;
+        let frame = Arc::new(frame);
         frames.push(frame);
     }
     let mut transitions = Vec::with_capacity(max_size);
-    let mut transitions_file = open_file_buf_read(path.join("transitions")).unwrap();
-    while has_data_left(&mut transitions_file).unwrap() {
-        let saved_transition: SavedTransition =
-            bincode::deserialize_from(&mut transitions_file).unwrap();
-        let state = saved_transition
-            .state
-            .frames()
-            .map(|frame_index| Rc::clone(&frames[frame_index]));
-        let next_state = saved_transition
-            .next_state
-            .frames()
-            .map(|frame_index| Rc::clone(&frames[frame_index]));
-        let transition = CompressedRcTransition {
-            state: state.into(),
-            next_state: next_state.into(),
+    let transitions_path = path.join("transitions");
+    let mut transitions_file = open_file_buf_read(&transitions_path)
+        .map_err(|e| LoadError::Io(transitions_path.clone(), e))?;
+    while has_data_left(&mut transitions_file)
+        .map_err(|e| LoadError::Io(transitions_path.clone(), e))?
+    {
+        let saved_transition: SavedTransition = bincode::deserialize_from(&mut transitions_file)
+            .map_err(|e| LoadError::Decode(transitions_path.clone(), e))?;
+        let transition = CompressedArcTransition {
+            state: load_state(&frames, &saved_transition.state)?,
+            next_state: load_state(&frames, &saved_transition.next_state)?,
             action: saved_transition.action,
             reward: saved_transition.reward,
             terminated: saved_transition.terminated,
         };
         transitions.push(transition);
     }
-    transitions
+    Ok(transitions)
 }
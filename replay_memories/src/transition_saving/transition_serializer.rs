@@ -1,11 +1,11 @@
 use replay_data::{
-    CompressedImageOwned2, CompressedRcState, CompressedRcTransition, SavedState, SavedTransition,
+    CompressedArcState, CompressedArcTransition, CompressedImageOwned2, SavedState, SavedTransition,
 };
 use std::collections::HashMap;
-use std::rc::Rc;
+use std::sync::Arc;
 
 pub struct TransitionSerializer<'a> {
-    frames: Vec<&'a Rc<CompressedImageOwned2>>,
+    frames: Vec<&'a Arc<CompressedImageOwned2>>,
     transitions: Vec<SavedTransition>,
     frame_pointers_to_indices: HashMap<*const CompressedImageOwned2, usize>,
 }
@@ -22,9 +22,9 @@ impl<'a> TransitionSerializer<'a> {
     pub fn run<I>(
         mut self,
         transitions: I,
-    ) -> (Vec<&'a Rc<CompressedImageOwned2>>, Vec<SavedTransition>)
+    ) -> (Vec<&'a Arc<CompressedImageOwned2>>, Vec<SavedTransition>)
     where
-        I: IntoIterator<Item = &'a CompressedRcTransition>,
+        I: IntoIterator<Item = &'a CompressedArcTransition>,
     {
         for transition in transitions {
             self.receive_transition(transition);
@@ -32,7 +32,7 @@ impl<'a> TransitionSerializer<'a> {
         (self.frames, self.transitions)
     }
 
-    fn receive_transition(&mut self, transition: &'a CompressedRcTransition) {
+    fn receive_transition(&mut self, transition: &'a CompressedArcTransition) {
         let transition = SavedTransition {
             state: self.receive_state(&transition.state),
             next_state: self.receive_state(&transition.next_state),
@@ -43,22 +43,23 @@ impl<'a> TransitionSerializer<'a> {
         self.transitions.push(transition);
     }
 
-    fn receive_state(&mut self, state: &'a CompressedRcState) -> SavedState {
-        let state_frame_indices = state
+    fn receive_state(&mut self, state: &'a CompressedArcState) -> SavedState {
+        let state_frame_indices: Vec<_> = state
             .frames()
-            .each_ref()
-            .map(|frame| self.receive_frame(frame));
+            .iter()
+            .map(|frame| self.receive_frame(frame))
+            .collect();
         state_frame_indices.into()
     }
 
-    fn receive_frame(&mut self, frame: &'a Rc<CompressedImageOwned2>) -> usize {
-        if let Some(frame_index) = self.frame_pointers_to_indices.get(&Rc::as_ptr(frame)) {
+    fn receive_frame(&mut self, frame: &'a Arc<CompressedImageOwned2>) -> usize {
+        if let Some(frame_index) = self.frame_pointers_to_indices.get(&Arc::as_ptr(frame)) {
             *frame_index
         } else {
             let frame_index = self.frames.len();
             self.frames.push(frame);
             self.frame_pointers_to_indices
-                .insert(Rc::as_ptr(frame), frame_index);
+                .insert(Arc::as_ptr(frame), frame_index);
             frame_index
         }
     }
@@ -1,6 +1,7 @@
 mod prioritized_replay_wrapper;
 mod queue_replay_wrapper;
 mod remote_replay_wrapper;
+mod training;
 
 pub use prioritized_replay_wrapper::PrioritizedReplayWrapper;
 pub use queue_replay_wrapper::QueueReplayWrapper;
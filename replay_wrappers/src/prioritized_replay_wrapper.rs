@@ -1,78 +1,170 @@
+use crate::training;
+use file_io::LoadError;
 use model::traits::{Actor, Persistable, PrioritizedLearner, TargetNet};
 use model::LearningStepInfo;
-use replay_data::CompressedRcTransition;
-use replay_memories::ReplayPrioritized;
+use replay_memories::{
+    PersistentMemory, Prioritization, PrioritizedMemory, ReplayPrioritized, ReplayShared,
+};
 use std::fs;
 use std::path::Path;
 
-pub struct PrioritizedReplayWrapper<T> {
+pub struct PrioritizedReplayWrapper<T, M = ReplayPrioritized> {
     model: T,
-    memory: ReplayPrioritized,
-    alpha: f64,
+    memory: M,
 }
 
 impl<T> PrioritizedReplayWrapper<T> {
-    pub fn wrap(model: T, memory_capacity: usize, alpha: f64) -> Self {
-        Self {
+    pub fn wrap(
+        model: T,
+        memory_capacity: usize,
+        alpha: f64,
+        prioritization: Prioritization,
+    ) -> Self {
+        Self::wrap_memory(
             model,
-            memory: ReplayPrioritized::with_max_size(memory_capacity),
-            alpha,
-        }
+            ReplayPrioritized::with_prioritization(memory_capacity, prioritization, alpha),
+        )
     }
-    pub fn remember(&mut self, transition: CompressedRcTransition) {
-        self.memory.add_transition(transition);
+    pub fn wrap_saved_memory<P: AsRef<Path>>(model: T, memory_path: P) -> Result<Self, LoadError> {
+        Ok(Self::wrap_memory(
+            model,
+            ReplayPrioritized::from_saved(memory_path)?,
+        ))
     }
 }
 
-impl<T: Actor<State>, State> Actor<State> for PrioritizedReplayWrapper<T> {
+impl<T, M: PrioritizedMemory> PrioritizedReplayWrapper<T, M> {
+    pub fn wrap_memory(model: T, memory: M) -> Self {
+        Self { model, memory }
+    }
+    pub fn remember(&mut self, transition: M::Transition) {
+        self.memory.insert(transition, None);
+    }
+    // rebuilds the memory's priorities, so it shouldn't be called on
+    // every step
+    pub fn set_alpha(&mut self, alpha: f64) {
+        self.memory.set_alpha(alpha);
+    }
+    pub fn model(&self) -> &T {
+        &self.model
+    }
+    pub fn memory_len(&self) -> usize {
+        self.memory.len()
+    }
+}
+
+impl<T: Actor<State>, State, M> Actor<State> for PrioritizedReplayWrapper<T, M> {
     fn best_action(&self, state: &State) -> u8 {
         self.model.best_action(state)
     }
+    fn q_vals(&self, state: &State) -> Vec<f32> {
+        self.model.q_vals(state)
+    }
+    fn q_vals_batch(&self, states: &[&State]) -> Vec<Vec<f32>> {
+        self.model.q_vals_batch(states)
+    }
 }
 
-impl<T: PrioritizedLearner<CompressedRcTransition>> PrioritizedReplayWrapper<T> {
+impl<T: PrioritizedLearner<M::Transition>, M: PrioritizedMemory> PrioritizedReplayWrapper<T, M> {
     pub fn train_step(&mut self, beta: f64) -> Option<LearningStepInfo> {
         const BATCH_SIZE: usize = 32;
-        if self.memory.len() >= BATCH_SIZE {
-            let (batch_indices, batch_probabilities, batch_transitions) =
-                self.memory.sample_batch(BATCH_SIZE);
-            let min_probability = self.memory.min_probability();
-            let (step_info, batch_abs_td_errors) = self.model.train_batch_prioritized(
-                &batch_transitions,
-                &batch_probabilities,
-                min_probability,
-                self.memory.len(),
-                beta,
-            );
-            self.memory.update_priorities_with_td_errors(
-                &batch_indices,
-                &batch_abs_td_errors,
-                self.alpha,
-            );
-            Some(step_info)
-        } else {
-            None
-        }
+        training::train_prioritized(&mut self.model, &mut self.memory, BATCH_SIZE, beta).ok()
     }
 }
 
-impl<T: TargetNet> TargetNet for PrioritizedReplayWrapper<T> {
+impl<T: TargetNet, M> TargetNet for PrioritizedReplayWrapper<T, M> {
     fn copy_control_to_target(&mut self) {
         self.model.copy_control_to_target();
     }
 }
 
-impl<T: Persistable> Persistable for PrioritizedReplayWrapper<T> {
-    fn save<P: AsRef<Path>>(&self, path: P) {
+impl<T: Persistable, M: PersistentMemory> PrioritizedReplayWrapper<T, M> {
+    pub fn save<P: AsRef<Path>>(&self, path: P) {
         let path = path.as_ref();
         self.model.save(path.join("model_vars"));
         let memory_path = path.join("memory");
         fs::create_dir_all(&memory_path).unwrap();
         self.memory.save(memory_path);
     }
-    fn load<P: AsRef<Path>>(&mut self, path: P) {
+    // the memory is loaded first, so that the model is left as it was if
+    // the memory can't be loaded
+    pub fn load<P: AsRef<Path>>(&mut self, path: P) -> Result<(), LoadError> {
         let path = path.as_ref();
+        self.memory.load(path.join("memory"))?;
         self.model.load(path.join("model_vars"));
-        self.memory.load(path.join("memory"));
+        Ok(())
+    }
+}
+
+impl<T: Persistable> PrioritizedReplayWrapper<T, ReplayShared> {
+    // like save, but the memory, which takes by far the longest, is written
+    // on its own thread while the model is saved
+    pub fn save_concurrently<P: AsRef<Path>>(&self, path: P) {
+        let path = path.as_ref();
+        let memory_path = path.join("memory");
+        fs::create_dir_all(&memory_path).unwrap();
+        let memory = &self.memory;
+        std::thread::scope(|scope| {
+            scope.spawn(|| memory.save(memory_path));
+            self.model.save(path.join("model_vars"));
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::ImageOwned2;
+    use model::{ModelMetadata, ReferenceModel};
+    use replay_data::{CompressedArcState, CompressedArcTransition, CompressedImageOwned2};
+    use std::sync::Arc;
+
+    const WIDTH: u32 = 2;
+    const HEIGHT: u32 = 2;
+
+    fn frame(value: u8) -> Arc<CompressedImageOwned2> {
+        let image = ImageOwned2::new(WIDTH, HEIGHT, vec![value; (2 * WIDTH * HEIGHT) as usize]);
+        Arc::new((&image).into())
+    }
+
+    fn state(value: u8) -> CompressedArcState {
+        [frame(value), frame(value.wrapping_add(1))].into()
+    }
+
+    // a reference model trained through the wrapper must change its q values
+    // and the priorities of the transitions that it trained on
+    #[test]
+    fn trains_reference_model() {
+        let model = ReferenceModel::from_metadata(ModelMetadata {
+            n_actions: 3,
+            n_steps: 3,
+            gamma: 0.99,
+            state_shape: [4, HEIGHT.into(), WIDTH.into()],
+        });
+        let mut agent =
+            PrioritizedReplayWrapper::wrap(model, 64, 0.6, Prioritization::Proportional);
+        for i in 0..40 {
+            agent.remember(CompressedArcTransition {
+                state: state(i),
+                next_state: state(i + 1),
+                action: i % 3,
+                reward: f64::from(i % 2),
+                terminated: i % 10 == 9,
+            });
+        }
+        assert_eq!(agent.memory_len(), 40);
+        let initial_priorities: Vec<_> = agent.memory.iter().map(|(_, p, _)| p).collect();
+        assert!(initial_priorities
+            .iter()
+            .all(|&priority| priority == initial_priorities[0]));
+
+        let step_info = agent
+            .train_step(0.4)
+            .expect("the memory holds enough transitions");
+        assert!(step_info.loss.is_finite() && step_info.loss > 0.0);
+
+        let priorities: Vec<_> = agent.memory.iter().map(|(_, p, _)| p).collect();
+        assert_ne!(priorities, initial_priorities);
+        assert!(agent.q_vals(&state(0)).iter().any(|&q_val| q_val != 0.0));
     }
 }
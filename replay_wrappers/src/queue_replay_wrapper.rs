@@ -1,63 +1,70 @@
+use crate::training;
+use file_io::LoadError;
 use model::traits::{Actor, BasicLearner, Persistable, TargetNet};
 use model::LearningStepInfo;
-use replay_data::CompressedRcTransition;
-use replay_memories::ReplayQueue;
+use replay_memories::{PersistentMemory, ReplayMemory, ReplayQueue};
 use std::fs;
 use std::path::Path;
 
-pub struct QueueReplayWrapper<T> {
+pub struct QueueReplayWrapper<T, M = ReplayQueue> {
     model: T,
-    memory: ReplayQueue,
+    memory: M,
 }
 
 impl<T> QueueReplayWrapper<T> {
     pub fn wrap(model: T, memory_capacity: usize) -> Self {
-        Self {
-            model,
-            memory: ReplayQueue::with_max_size(memory_capacity),
-        }
+        Self::wrap_memory(model, ReplayQueue::with_max_size(memory_capacity))
     }
-    pub fn remember(&mut self, transition: CompressedRcTransition) {
-        self.memory.add_transition(transition);
+}
+
+impl<T, M: ReplayMemory> QueueReplayWrapper<T, M> {
+    pub fn wrap_memory(model: T, memory: M) -> Self {
+        Self { model, memory }
+    }
+    pub fn remember(&mut self, transition: M::Transition) {
+        self.memory.insert(transition, None);
     }
 }
 
-impl<T: Actor<State>, State> Actor<State> for QueueReplayWrapper<T> {
+impl<T: Actor<State>, State, M> Actor<State> for QueueReplayWrapper<T, M> {
     fn best_action(&self, state: &State) -> u8 {
         self.model.best_action(state)
     }
+    fn q_vals(&self, state: &State) -> Vec<f32> {
+        self.model.q_vals(state)
+    }
+    fn q_vals_batch(&self, states: &[&State]) -> Vec<Vec<f32>> {
+        self.model.q_vals_batch(states)
+    }
 }
 
-impl<T: BasicLearner<CompressedRcTransition>> QueueReplayWrapper<T> {
+impl<T: BasicLearner<M::Transition>, M: ReplayMemory> QueueReplayWrapper<T, M> {
     pub fn train_step(&mut self) -> Option<LearningStepInfo> {
         const BATCH_SIZE: usize = 32;
-        if self.memory.len() >= BATCH_SIZE {
-            let batch_transitions = self.memory.sample_batch(BATCH_SIZE);
-            let step_info = self.model.train_batch(&batch_transitions);
-            Some(step_info)
-        } else {
-            None
-        }
+        training::train_uniform(&mut self.model, &self.memory, BATCH_SIZE).ok()
     }
 }
 
-impl<T: TargetNet> TargetNet for QueueReplayWrapper<T> {
+impl<T: TargetNet, M> TargetNet for QueueReplayWrapper<T, M> {
     fn copy_control_to_target(&mut self) {
         self.model.copy_control_to_target();
     }
 }
 
-impl<T: Persistable> Persistable for QueueReplayWrapper<T> {
-    fn save<P: AsRef<Path>>(&self, path: P) {
+impl<T: Persistable, M: PersistentMemory> QueueReplayWrapper<T, M> {
+    pub fn save<P: AsRef<Path>>(&self, path: P) {
         let path = path.as_ref();
         self.model.save(path.join("model_vars").to_str().unwrap());
         let memory_path = path.join("memory");
         fs::create_dir_all(&memory_path).unwrap();
         self.memory.save(memory_path);
     }
-    fn load<P: AsRef<Path>>(&mut self, path: P) {
+    // the memory is loaded first, so that the model is left as it was if
+    // the memory can't be loaded
+    pub fn load<P: AsRef<Path>>(&mut self, path: P) -> Result<(), LoadError> {
         let path = path.as_ref();
+        self.memory.load(path.join("memory"))?;
         self.model.load(path.join("model_vars").to_str().unwrap());
-        self.memory.load(path.join("memory"));
+        Ok(())
     }
 }
@@ -1,25 +1,36 @@
-use model::traits::{Actor, ParamFetcher, Persistable, PrioritizedLearner, TargetNet};
-use model::{BasicModel, LearningStepInfo, Params};
-use packets::{PriorityUpdate, SampleBatchErrorKind, SampleBatchReply, SampleBatchResult};
-use replay_data::CompressedTransition;
-use replay_memories::ReplayRemote;
+use crate::training;
+use file_io::LoadError;
+use model::traits::{
+    Actor, ParamFetcher, Persistable, PrioritizedLearner, TargetNet, TdErrorEstimator,
+};
+use model::{LearningStepInfo, Params};
+use replay_data::{CompressedTransition, TransitionMetadata};
+use replay_memories::{PersistentMemory, PrioritizedMemory, ReplayRemote, SampleError};
 use std::fs;
+use std::mem;
 use std::net::SocketAddr;
 use std::path::Path;
 
-pub struct RemoteReplayWrapper<T> {
+// the number of transitions that are gathered before they are inserted, so
+// that their priorities are computed in a single batch and they are sent to
+// the replay server in a single request
+const INSERTION_BATCH_LEN: usize = 50;
+
+// Wraps a memory that is shared with other processes, which may be missing if
+// the process doesn't need one. Sampling may therefore fail for reasons out of
+// this process' control, so failed samples are followed by a delay
+pub struct RemoteReplayWrapper<T, M = ReplayRemote> {
     model: T,
-    memory: Option<ReplayRemote>,
-    alpha: f64,
+    memory: Option<M>,
+    computes_initial_priorities: bool,
+    // transitions waiting to be inserted, so that their priorities can be
+    // computed in a single batch
+    pending_transitions: Vec<(CompressedTransition, Option<TransitionMetadata>)>,
 }
 
 impl<T> RemoteReplayWrapper<T> {
-    pub fn wrap(model: T, replay_server_addr: Option<SocketAddr>, alpha: f64) -> Self {
-        Self {
-            model,
-            memory: replay_server_addr.map(ReplayRemote::new),
-            alpha,
-        }
+    pub fn wrap(model: T, replay_server_addr: Option<SocketAddr>) -> Self {
+        Self::wrap_memory(model, replay_server_addr.map(ReplayRemote::new))
     }
 
     pub fn truncate_memory(&mut self) {
@@ -27,102 +38,128 @@ impl<T> RemoteReplayWrapper<T> {
             memory.truncate()
         }
     }
+}
+
+impl<T, M: PrioritizedMemory> RemoteReplayWrapper<T, M> {
+    pub fn wrap_memory(model: T, memory: Option<M>) -> Self {
+        Self {
+            model,
+            memory,
+            computes_initial_priorities: true,
+            pending_transitions: vec![],
+        }
+    }
 
-    fn convert_abs_td_error_to_priority(&self, abs_td_error: f64) -> f64 {
-        const EPSILON: f64 = 0.001;
-        (abs_td_error + EPSILON).powf(self.alpha)
+    // when not set, transitions are inserted without a priority and the
+    // replay server assigns one itself, which saves computing a td error for
+    // every transition
+    pub fn set_computes_initial_priorities(&mut self, computes_initial_priorities: bool) {
+        self.computes_initial_priorities = computes_initial_priorities;
     }
 
-    fn update_priorities_from_td_errors(&mut self, indices: &[usize], abs_td_errors: &[f64]) {
-        let priorities = abs_td_errors
-            .iter()
-            .map(|abs_td_error| self.convert_abs_td_error_to_priority(*abs_td_error));
-        let updates = priorities
-            .zip(indices)
-            .map(|(priority, index)| PriorityUpdate {
-                index: *index,
-                priority,
-            });
-        let updates = updates.collect();
+    pub fn set_alpha(&mut self, alpha: f64) {
         if let Some(ref mut memory) = self.memory {
-            memory.update_priorities(updates);
+            memory.set_alpha(alpha)
         }
     }
 }
 
-impl RemoteReplayWrapper<BasicModel> {
-    fn compute_priority(&self, transition: &CompressedTransition) -> f64 {
-        let abs_td_error: f64 = self.model.compute_abs_td_errors(&[transition])[0].into();
-        self.convert_abs_td_error_to_priority(abs_td_error)
+impl<T, M> RemoteReplayWrapper<T, M>
+where
+    T: TdErrorEstimator<CompressedTransition>,
+    M: PrioritizedMemory<Transition = CompressedTransition>,
+{
+    pub fn remember(
+        &mut self,
+        transition: CompressedTransition,
+        metadata: Option<TransitionMetadata>,
+    ) {
+        if self.memory.is_none() {
+            return;
+        }
+        self.pending_transitions.push((transition, metadata));
+        if self.pending_transitions.len() >= INSERTION_BATCH_LEN {
+            self.flush_transitions();
+        }
     }
 
-    pub fn remember(&mut self, transition: CompressedTransition) {
-        let priority = self.compute_priority(&transition);
-        if let Some(ref mut memory) = self.memory {
-            memory.add_transition_with_priority(transition, priority);
+    fn flush_transitions(&mut self) {
+        let transitions = mem::take(&mut self.pending_transitions);
+        let Some(ref mut memory) = self.memory else {
+            return;
+        };
+        if self.computes_initial_priorities {
+            let abs_td_errors = self.model.compute_abs_td_errors(
+                &transitions
+                    .iter()
+                    .map(|(transition, _metadata)| transition)
+                    .collect::<Vec<_>>(),
+            );
+            memory.insert_batch_with_abs_td_errors(
+                transitions
+                    .into_iter()
+                    .zip(abs_td_errors)
+                    .map(|((transition, metadata), abs_td_error)| {
+                        (transition, abs_td_error, metadata)
+                    })
+                    .collect(),
+            );
+        } else {
+            memory.insert_batch(transitions);
         }
     }
 }
 
-impl<T: Actor<State>, State> Actor<State> for RemoteReplayWrapper<T> {
+impl<T: Actor<State>, State, M> Actor<State> for RemoteReplayWrapper<T, M> {
     fn best_action(&self, state: &State) -> u8 {
         self.model.best_action(state)
     }
-}
-
-impl<T: PrioritizedLearner<CompressedTransition>> RemoteReplayWrapper<T> {
-    fn train_on_sampled_batch(&mut self, reply: SampleBatchReply, beta: f64) -> LearningStepInfo {
-        let SampleBatchReply {
-            batch,
-            min_probability,
-            replay_len,
-        } = reply;
-        let (indices, probabilities, transitions) = batch;
-        let (step_info, abs_td_errors) = self.model.train_batch_prioritized(
-            &transitions.iter().collect::<Vec<_>>(),
-            &probabilities,
-            min_probability,
-            replay_len,
-            beta,
-        );
-        self.update_priorities_from_td_errors(&indices, &abs_td_errors);
-        step_info
+    fn q_vals(&self, state: &State) -> Vec<f32> {
+        self.model.q_vals(state)
+    }
+    fn q_vals_batch(&self, states: &[&State]) -> Vec<Vec<f32>> {
+        self.model.q_vals_batch(states)
     }
+}
 
+impl<T: PrioritizedLearner<M::Transition>, M: PrioritizedMemory> RemoteReplayWrapper<T, M> {
     pub fn train_step(&mut self, beta: f64) -> Option<LearningStepInfo> {
         const BATCH_SIZE: usize = 512;
-        if let Some(ref memory) = self.memory {
-            match memory.sample_batch(BATCH_SIZE) {
-                SampleBatchResult::Ok(reply) => Some(self.train_on_sampled_batch(reply, beta)),
-                SampleBatchResult::Err(err) => match err {
-                    SampleBatchErrorKind::NotEnoughTransitions => {
-                        // When there aren't enough transitions to sample a batch,
-                        // the model has nothing to predict, which means that
-                        // training steps are extremely fast. Since sample requests
-                        // are transmitted over TCP, repeated sample requests at
-                        // such a high rate may actually cause the machine to run
-                        // out of ephemeral ports, resulting in connection errors.
-                        // Therefore, we simulate a slight delay to avoid
-                        // overwhelming the machine with requests
-                        std::thread::sleep(std::time::Duration::from_millis(500));
-                        None
-                    }
-                },
+        let memory = self.memory.as_mut()?;
+        match training::train_prioritized(&mut self.model, memory, BATCH_SIZE, beta) {
+            Ok(step_info) => Some(step_info),
+            Err(SampleError::NotEnoughTransitions) => {
+                // When there aren't enough transitions to sample a batch,
+                // the model has nothing to predict, which means that
+                // training steps are extremely fast. Since sample requests
+                // are transmitted over TCP, repeated sample requests at
+                // such a high rate may actually cause the machine to run
+                // out of ephemeral ports, resulting in connection errors.
+                // Therefore, we simulate a slight delay to avoid
+                // overwhelming the machine with requests
+                std::thread::sleep(std::time::Duration::from_millis(500));
+                None
+            }
+            Err(SampleError::RateLimited) => {
+                // The actors haven't inserted enough fresh transitions
+                // to keep up with the configured samples per insert
+                // ratio. For the same reason as above, we wait a bit
+                // before the next request
+                std::thread::sleep(std::time::Duration::from_millis(100));
+                None
             }
-        } else {
-            None
         }
     }
 }
 
-impl<T: TargetNet> TargetNet for RemoteReplayWrapper<T> {
+impl<T: TargetNet, M> TargetNet for RemoteReplayWrapper<T, M> {
     fn copy_control_to_target(&mut self) {
         self.model.copy_control_to_target();
     }
 }
 
-impl<T: Persistable> Persistable for RemoteReplayWrapper<T> {
-    fn save<P: AsRef<Path>>(&self, path: P) {
+impl<T: Persistable, M: PersistentMemory> RemoteReplayWrapper<T, M> {
+    pub fn save<P: AsRef<Path>>(&self, path: P) {
         let path = path.as_ref();
         self.model.save(path.join("model_vars"));
         let memory_path = path.join("memory");
@@ -131,16 +168,19 @@ impl<T: Persistable> Persistable for RemoteReplayWrapper<T> {
             memory.save(memory_path);
         }
     }
-    fn load<P: AsRef<Path>>(&mut self, path: P) {
+    // the memory is loaded first, so that the model is left as it was if
+    // the memory can't be loaded
+    pub fn load<P: AsRef<Path>>(&mut self, path: P) -> Result<(), LoadError> {
         let path = path.as_ref();
-        self.model.load(path.join("model_vars"));
         if let Some(ref mut memory) = self.memory {
-            memory.load(path.join("memory"));
+            memory.load(path.join("memory"))?;
         }
+        self.model.load(path.join("model_vars"));
+        Ok(())
     }
 }
 
-impl<T: ParamFetcher> ParamFetcher for RemoteReplayWrapper<T> {
+impl<T: ParamFetcher, M> ParamFetcher for RemoteReplayWrapper<T, M> {
     fn params(&self) -> Params {
         self.model.params()
     }
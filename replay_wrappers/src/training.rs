@@ -0,0 +1,50 @@
+use model::traits::{BasicLearner, PrioritizedLearner};
+use model::LearningStepInfo;
+use replay_memories::{PrioritizedMemory, ReplayMemory, SampleError, SampledBatch};
+use std::borrow::Borrow;
+
+pub fn train_uniform<T, M>(
+    model: &mut T,
+    memory: &M,
+    batch_size: usize,
+) -> Result<LearningStepInfo, SampleError>
+where
+    T: BasicLearner<M::Transition>,
+    M: ReplayMemory,
+{
+    let batch = memory.sample(batch_size)?;
+    let transitions: Vec<&M::Transition> = batch.transitions.iter().map(Borrow::borrow).collect();
+    Ok(model.train_batch(&transitions))
+}
+
+// trains on a batch sampled by priority, then updates the priorities of the
+// batch with the resulting td errors
+pub fn train_prioritized<T, M>(
+    model: &mut T,
+    memory: &mut M,
+    batch_size: usize,
+    beta: f64,
+) -> Result<LearningStepInfo, SampleError>
+where
+    T: PrioritizedLearner<M::Transition>,
+    M: PrioritizedMemory,
+{
+    let SampledBatch {
+        indices,
+        probabilities,
+        transitions,
+        min_probability,
+        memory_len,
+    } = memory.sample(batch_size)?;
+    let (step_info, abs_td_errors) = model.train_batch_prioritized(
+        &transitions.iter().map(Borrow::borrow).collect::<Vec<_>>(),
+        &probabilities,
+        min_probability,
+        memory_len,
+        beta,
+    );
+    // the sampled transitions may borrow from the memory
+    drop(transitions);
+    memory.update_priorities_with_td_errors(&indices, &abs_td_errors);
+    Ok(step_info)
+}